@@ -0,0 +1,871 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha512_256};
+use thiserror::Error;
+
+use crate::{
+    compilation_error::CompilationError,
+    context::CompilationContext,
+    expression::{primitive::Primitive, Expr, Expression},
+    typesig,
+    typing::{TypeEnum, TypePrimitive, TypeVar},
+    with_error_message,
+};
+
+/// ARC-4 types that values can be encoded as.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiType {
+    /// `uint<N>`, with `8 <= N <= 512` and `N % 8 == 0`
+    Uint(usize),
+    /// `ufixed<N>x<M>`, with `N` as for `uint<N>` and `1 <= M <= 160`
+    Ufixed(usize, usize),
+    Byte,
+    Bool,
+    Address,
+    String,
+    /// `T[N]`
+    StaticArray(Box<AbiType>, usize),
+    /// `T[]`
+    DynamicArray(Box<AbiType>),
+    /// `(T1,T2,...)`
+    Tuple(Vec<AbiType>),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum AbiError {
+    #[error("Invalid ABI type {0}")]
+    InvalidType(String),
+    #[error("Value {1:?} cannot be encoded as {0}")]
+    TypeMismatch(AbiType, AbiValue),
+    #[error("{0} expects {1} elements, got {2}")]
+    LengthMismatch(AbiType, usize, usize),
+    #[error("Encoding of {0} exceeds 65535 bytes")]
+    TooLong(AbiType),
+    #[error("Malformed encoding of {0}")]
+    Malformed(AbiType),
+}
+
+fn valid_bits(n: usize) -> bool {
+    (8..=512).contains(&n) && n.is_multiple_of(8)
+}
+
+/// Splits the elements of a tuple type at top-level commas.
+fn split_tuple(inner: &str) -> Vec<&str> {
+    let mut elements = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                elements.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !inner.is_empty() {
+        elements.push(&inner[start..]);
+    }
+    elements
+}
+
+fn parse_type(s: &str) -> Result<AbiType, AbiError> {
+    let invalid = || AbiError::InvalidType(s.to_string());
+    if let Some(inner) = s.strip_suffix(']') {
+        let open = inner.rfind('[').ok_or_else(invalid)?;
+        let element = Box::new(parse_type(&inner[..open])?);
+        return match &inner[open + 1..] {
+            "" => Ok(AbiType::DynamicArray(element)),
+            n => Ok(AbiType::StaticArray(
+                element,
+                n.parse().map_err(|_| invalid())?,
+            )),
+        };
+    }
+    if let Some(inner) = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        return split_tuple(inner)
+            .into_iter()
+            .map(parse_type)
+            .collect::<Result<_, _>>()
+            .map(AbiType::Tuple);
+    }
+    match s {
+        "byte" => return Ok(AbiType::Byte),
+        "bool" => return Ok(AbiType::Bool),
+        "address" => return Ok(AbiType::Address),
+        "string" => return Ok(AbiType::String),
+        _ => {}
+    }
+    if let Some(n) = s.strip_prefix("uint") {
+        return match n.parse() {
+            Ok(n) if valid_bits(n) => Ok(AbiType::Uint(n)),
+            _ => Err(invalid()),
+        };
+    }
+    if let Some((n, m)) = s.strip_prefix("ufixed").and_then(|s| s.split_once('x')) {
+        return match (n.parse(), m.parse()) {
+            (Ok(n), Ok(m)) if valid_bits(n) && (1..=160).contains(&m) => Ok(AbiType::Ufixed(n, m)),
+            _ => Err(invalid()),
+        };
+    }
+    Err(invalid())
+}
+
+impl FromStr for AbiType {
+    type Err = AbiError;
+
+    /// Parses a type as written in signatures, ignoring whitespace.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_type(&s.split_whitespace().collect::<String>())
+    }
+}
+
+impl Display for AbiType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbiType::Uint(n) => write!(f, "uint{n}"),
+            AbiType::Ufixed(n, m) => write!(f, "ufixed{n}x{m}"),
+            AbiType::Byte => write!(f, "byte"),
+            AbiType::Bool => write!(f, "bool"),
+            AbiType::Address => write!(f, "address"),
+            AbiType::String => write!(f, "string"),
+            AbiType::StaticArray(t, n) => write!(f, "{t}[{n}]"),
+            AbiType::DynamicArray(t) => write!(f, "{t}[]"),
+            AbiType::Tuple(ts) => {
+                let ts = ts.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+                write!(f, "({})", ts.join(","))
+            }
+        }
+    }
+}
+
+impl Serialize for AbiType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AbiType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        AbiType::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl AbiType {
+    /// Type of the stack value that is encoded as this ABI type. Byte strings
+    /// are held without their length prefix, while other arrays and tuples are
+    /// held in their encoded form.
+    pub fn stack_type(&self) -> TypeEnum {
+        match self {
+            AbiType::Uint(n) | AbiType::Ufixed(n, _) if *n <= 64 => typesig!(int),
+            AbiType::Uint(_) | AbiType::Ufixed(..) => typesig!(biguint),
+            AbiType::Byte | AbiType::Bool => typesig!(int),
+            _ => typesig!(bytes),
+        }
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiType::String | AbiType::DynamicArray(_) => true,
+            AbiType::StaticArray(t, _) => t.is_dynamic(),
+            AbiType::Tuple(ts) => ts.iter().any(AbiType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// Size of the encoding of static types.
+    pub fn static_size(&self) -> Option<usize> {
+        if self.is_dynamic() {
+            return None;
+        }
+        Some(match self {
+            AbiType::Uint(n) | AbiType::Ufixed(n, _) => n / 8,
+            AbiType::Byte | AbiType::Bool => 1,
+            AbiType::Address => 32,
+            AbiType::StaticArray(t, n) if **t == AbiType::Bool => n.div_ceil(8),
+            AbiType::StaticArray(t, n) => t.static_size()? * n,
+            AbiType::Tuple(ts) => tuple_head_size(ts),
+            AbiType::String | AbiType::DynamicArray(_) => unreachable!(),
+        })
+    }
+
+    /// Length of fixed-length byte strings (`address` and `byte[N]`).
+    fn fixed_bytes_len(&self) -> Option<usize> {
+        match self {
+            AbiType::Address => Some(32),
+            AbiType::StaticArray(t, n) if **t == AbiType::Byte => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Encodes `value` off-chain.
+    pub fn encode(&self, value: &AbiValue) -> Result<Vec<u8>, AbiError> {
+        let mismatch = || AbiError::TypeMismatch(self.clone(), value.clone());
+        match (self, value) {
+            (AbiType::Uint(n) | AbiType::Ufixed(n, _), AbiValue::Uint(bytes)) => {
+                let size = n / 8;
+                if bytes.len() > size {
+                    return Err(mismatch());
+                }
+                let mut encoded = vec![0; size - bytes.len()];
+                encoded.extend(bytes);
+                Ok(encoded)
+            }
+            (AbiType::Bool, AbiValue::Bool(b)) => Ok(vec![if *b { 0x80 } else { 0 }]),
+            (AbiType::Byte, AbiValue::Byte(b)) => Ok(vec![*b]),
+            (AbiType::Address, AbiValue::Address(a)) => Ok(a.to_vec()),
+            (AbiType::String, AbiValue::String(s)) => self.with_length(s.len(), s.as_bytes()),
+            (AbiType::StaticArray(t, n), AbiValue::Array(values)) => {
+                if values.len() != *n {
+                    return Err(AbiError::LengthMismatch(self.clone(), *n, values.len()));
+                }
+                encode_values(&vec![*t.clone(); *n], values)
+            }
+            (AbiType::DynamicArray(t), AbiValue::Array(values)) => self.with_length(
+                values.len(),
+                &encode_values(&vec![*t.clone(); values.len()], values)?,
+            ),
+            (AbiType::Tuple(ts), AbiValue::Tuple(values)) => {
+                if values.len() != ts.len() {
+                    return Err(AbiError::LengthMismatch(
+                        self.clone(),
+                        ts.len(),
+                        values.len(),
+                    ));
+                }
+                encode_values(ts, values)
+            }
+            _ => Err(mismatch()),
+        }
+    }
+
+    fn with_length(&self, length: usize, contents: &[u8]) -> Result<Vec<u8>, AbiError> {
+        let length = u16::try_from(length).map_err(|_| AbiError::TooLong(self.clone()))?;
+        let mut encoded = length.to_be_bytes().to_vec();
+        encoded.extend(contents);
+        Ok(encoded)
+    }
+
+    /// Decodes a value encoded as this type, which must span all of `bytes`.
+    pub fn decode(&self, bytes: &[u8]) -> Result<AbiValue, AbiError> {
+        let malformed = || AbiError::Malformed(self.clone());
+        let length = || match bytes {
+            [hi, lo, ..] => Ok(u16::from_be_bytes([*hi, *lo]) as usize),
+            _ => Err(malformed()),
+        };
+        match self {
+            AbiType::Uint(n) | AbiType::Ufixed(n, _) if bytes.len() == n / 8 => {
+                Ok(AbiValue::uint(bytes))
+            }
+            AbiType::Bool => match bytes {
+                [0x80] => Ok(AbiValue::Bool(true)),
+                [0] => Ok(AbiValue::Bool(false)),
+                _ => Err(malformed()),
+            },
+            AbiType::Byte => match bytes {
+                [b] => Ok(AbiValue::Byte(*b)),
+                _ => Err(malformed()),
+            },
+            AbiType::Address => bytes
+                .try_into()
+                .map(AbiValue::Address)
+                .map_err(|_| malformed()),
+            AbiType::String if bytes.len() == 2 + length()? => {
+                String::from_utf8(bytes[2..].to_vec())
+                    .map(AbiValue::String)
+                    .map_err(|_| malformed())
+            }
+            AbiType::StaticArray(t, n) => {
+                decode_values(&vec![*t.clone(); *n], bytes).map(AbiValue::Array)
+            }
+            AbiType::DynamicArray(t) => {
+                decode_values(&vec![*t.clone(); length()?], &bytes[2..]).map(AbiValue::Array)
+            }
+            AbiType::Tuple(ts) => decode_values(ts, bytes).map(AbiValue::Tuple),
+            _ => Err(malformed()),
+        }
+    }
+}
+
+/// Size of the head of a tuple: static elements are inlined, with runs of
+/// bools packed into bytes, and dynamic elements are 2-byte offsets.
+fn tuple_head_size(types: &[AbiType]) -> usize {
+    let mut size = 0;
+    let mut i = 0;
+    while i < types.len() {
+        let bools = bool_run(&types[i..]);
+        if bools > 0 {
+            size += bools.div_ceil(8);
+            i += bools;
+        } else {
+            size += types[i].static_size().unwrap_or(2);
+            i += 1;
+        }
+    }
+    size
+}
+
+fn bool_run(types: &[AbiType]) -> usize {
+    types.iter().take_while(|t| **t == AbiType::Bool).count()
+}
+
+fn encode_values(types: &[AbiType], values: &[AbiValue]) -> Result<Vec<u8>, AbiError> {
+    let mut heads = vec![];
+    let mut tails = vec![];
+    let mut i = 0;
+    while i < types.len() {
+        let bools = bool_run(&types[i..]).min(8);
+        if bools > 0 {
+            let mut byte = 0;
+            for (bit, value) in values[i..i + bools].iter().enumerate() {
+                match value {
+                    AbiValue::Bool(true) => byte |= 0x80 >> bit,
+                    AbiValue::Bool(false) => {}
+                    _ => return Err(AbiError::TypeMismatch(AbiType::Bool, value.clone())),
+                }
+            }
+            heads.push(vec![byte]);
+            tails.push(None);
+            i += bools;
+        } else if types[i].is_dynamic() {
+            heads.push(vec![0, 0]);
+            tails.push(Some(types[i].encode(&values[i])?));
+            i += 1;
+        } else {
+            heads.push(types[i].encode(&values[i])?);
+            tails.push(None);
+            i += 1;
+        }
+    }
+
+    let mut offset = heads.iter().map(Vec::len).sum::<usize>();
+    let mut tail = vec![];
+    for (head, encoded) in heads.iter_mut().zip(tails) {
+        if let Some(encoded) = encoded {
+            let head_offset = u16::try_from(offset)
+                .map_err(|_| AbiError::TooLong(AbiType::Tuple(types.to_vec())))?;
+            *head = head_offset.to_be_bytes().to_vec();
+            offset += encoded.len();
+            tail.extend(encoded);
+        }
+    }
+    let mut encoded = heads.concat();
+    encoded.extend(tail);
+    Ok(encoded)
+}
+
+fn decode_values(types: &[AbiType], bytes: &[u8]) -> Result<Vec<AbiValue>, AbiError> {
+    let malformed = || AbiError::Malformed(AbiType::Tuple(types.to_vec()));
+    let mut values = vec![];
+    let mut dynamic = vec![];
+    let mut position = 0;
+    let mut i = 0;
+    while i < types.len() {
+        let bools = bool_run(&types[i..]).min(8);
+        if bools > 0 {
+            let byte = bytes.get(position).ok_or_else(malformed)?;
+            values.extend((0..bools).map(|bit| Some(AbiValue::Bool(byte & (0x80 >> bit) != 0))));
+            position += 1;
+            i += bools;
+            continue;
+        }
+        match types[i].static_size() {
+            Some(size) => {
+                let encoded = bytes.get(position..position + size).ok_or_else(malformed)?;
+                values.push(Some(types[i].decode(encoded)?));
+                position += size;
+            }
+            None => {
+                let offset = bytes.get(position..position + 2).ok_or_else(malformed)?;
+                dynamic.push((i, u16::from_be_bytes([offset[0], offset[1]]) as usize));
+                values.push(None);
+                position += 2;
+            }
+        }
+        i += 1;
+    }
+
+    // each dynamic element extends to the start of the next one
+    for (k, &(index, offset)) in dynamic.iter().enumerate() {
+        let end = dynamic.get(k + 1).map_or(bytes.len(), |(_, next)| *next);
+        let encoded = bytes.get(offset..end).ok_or_else(malformed)?;
+        values[index] = Some(types[index].decode(encoded)?);
+    }
+    if dynamic.is_empty() && position != bytes.len() {
+        return Err(malformed());
+    }
+    Ok(values.into_iter().flatten().collect())
+}
+
+/// Values of ABI types, for producing and parsing encodings off-chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiValue {
+    /// `uint<N>`, or the scaled value of `ufixed<N>x<M>`, as big-endian bytes
+    /// without leading zeros (see [`AbiValue::uint`])
+    Uint(Vec<u8>),
+    Bool(bool),
+    Byte(u8),
+    Address([u8; 32]),
+    String(String),
+    /// Elements of static and dynamic arrays
+    Array(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+impl AbiValue {
+    /// An unsigned integer from big-endian bytes.
+    pub fn uint(bytes: &[u8]) -> Self {
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        AbiValue::Uint(bytes[start..].to_vec())
+    }
+
+    /// A `byte[N]` or `byte[]` value.
+    pub fn bytes(bytes: &[u8]) -> Self {
+        AbiValue::Array(bytes.iter().copied().map(AbiValue::Byte).collect())
+    }
+
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            AbiValue::Uint(bytes) if bytes.len() <= 16 => {
+                Some(bytes.iter().fold(0, |n, b| n << 8 | *b as u128))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<u64> for AbiValue {
+    fn from(n: u64) -> Self {
+        AbiValue::uint(&n.to_be_bytes())
+    }
+}
+
+impl From<u128> for AbiValue {
+    fn from(n: u128) -> Self {
+        AbiValue::uint(&n.to_be_bytes())
+    }
+}
+
+impl From<bool> for AbiValue {
+    fn from(b: bool) -> Self {
+        AbiValue::Bool(b)
+    }
+}
+
+impl From<&str> for AbiValue {
+    fn from(s: &str) -> Self {
+        AbiValue::String(s.to_string())
+    }
+}
+
+impl From<u8> for AbiValue {
+    fn from(b: u8) -> Self {
+        AbiValue::Byte(b)
+    }
+}
+
+impl From<[u8; 32]> for AbiValue {
+    fn from(address: [u8; 32]) -> Self {
+        AbiValue::Address(address)
+    }
+}
+
+impl From<&[u8]> for AbiValue {
+    fn from(bytes: &[u8]) -> Self {
+        AbiValue::bytes(bytes)
+    }
+}
+
+// Conversions of decoded values, giving the value back if it has another shape
+impl TryFrom<AbiValue> for u64 {
+    type Error = AbiValue;
+
+    fn try_from(value: AbiValue) -> Result<Self, Self::Error> {
+        value
+            .as_u128()
+            .and_then(|n| u64::try_from(n).ok())
+            .ok_or(value)
+    }
+}
+
+impl TryFrom<AbiValue> for u128 {
+    type Error = AbiValue;
+
+    fn try_from(value: AbiValue) -> Result<Self, Self::Error> {
+        value.as_u128().ok_or(value)
+    }
+}
+
+impl TryFrom<AbiValue> for bool {
+    type Error = AbiValue;
+
+    fn try_from(value: AbiValue) -> Result<Self, Self::Error> {
+        match value {
+            AbiValue::Bool(b) => Ok(b),
+            _ => Err(value),
+        }
+    }
+}
+
+impl TryFrom<AbiValue> for u8 {
+    type Error = AbiValue;
+
+    fn try_from(value: AbiValue) -> Result<Self, Self::Error> {
+        match value {
+            AbiValue::Byte(b) => Ok(b),
+            _ => Err(value),
+        }
+    }
+}
+
+impl TryFrom<AbiValue> for [u8; 32] {
+    type Error = AbiValue;
+
+    fn try_from(value: AbiValue) -> Result<Self, Self::Error> {
+        match value {
+            AbiValue::Address(address) => Ok(address),
+            _ => Err(value),
+        }
+    }
+}
+
+impl TryFrom<AbiValue> for String {
+    type Error = AbiValue;
+
+    fn try_from(value: AbiValue) -> Result<Self, Self::Error> {
+        match value {
+            AbiValue::String(s) => Ok(s),
+            _ => Err(value),
+        }
+    }
+}
+
+impl TryFrom<AbiValue> for Vec<u8> {
+    type Error = AbiValue;
+
+    fn try_from(value: AbiValue) -> Result<Self, Self::Error> {
+        match &value {
+            AbiValue::Array(values) => values
+                .iter()
+                .map(|v| match v {
+                    AbiValue::Byte(b) => Some(*b),
+                    _ => None,
+                })
+                .collect::<Option<_>>()
+                .ok_or(value),
+            _ => Err(value),
+        }
+    }
+}
+
+/// First 4 bytes of the SHA-512/256 hash of a method or event signature.
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = Sha512_256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn ops(ops: &[&str]) -> Vec<String> {
+    ops.iter().map(|op| op.to_string()).collect()
+}
+
+/// Converts the value on top of the stack to its ARC-4 encoding.
+pub(crate) fn encode(abi_type: &AbiType) -> Vec<String> {
+    match abi_type {
+        AbiType::Uint(64) | AbiType::Ufixed(64, _) => ops(&["itob"]),
+        AbiType::Uint(n) | AbiType::Ufixed(n, _) if *n < 64 => vec![
+            "dup".to_string(),
+            format!("int {}", 1u64 << n),
+            "<".to_string(),
+            with_error_message("assert", &format!("{abi_type} overflow")),
+            "itob".to_string(),
+            format!("extract {} {}", 8 - n / 8, n / 8),
+        ],
+        AbiType::Uint(n) | AbiType::Ufixed(n, _) => vec![
+            "dup".to_string(),
+            "len".to_string(),
+            format!("int {}", n / 8),
+            "<=".to_string(),
+            with_error_message("assert", &format!("{abi_type} overflow")),
+            format!("int {}", n / 8),
+            "bzero".to_string(),
+            "b|".to_string(),
+        ],
+        AbiType::Bool => ops(&["int 0", "!=", "int 128", "*", "itob", "extract 7 1"]),
+        AbiType::Byte => ops(&["itob", "extract 7 1"]),
+        AbiType::String => ops(&["dup", "len", "itob", "extract 6 2", "swap", "concat"]),
+        AbiType::DynamicArray(t) if **t == AbiType::Byte => {
+            ops(&["dup", "len", "itob", "extract 6 2", "swap", "concat"])
+        }
+        fixed => match fixed.fixed_bytes_len() {
+            Some(n) => vec![
+                "dup".to_string(),
+                "len".to_string(),
+                format!("int {n}"),
+                "==".to_string(),
+                with_error_message("assert", &format!("{fixed} has wrong length")),
+            ],
+            // already encoded
+            None => vec![],
+        },
+    }
+}
+
+/// Converts the ARC-4 encoding on top of the stack to a stack value.
+pub(crate) fn decode(abi_type: &AbiType) -> Vec<String> {
+    match abi_type {
+        AbiType::Uint(n) | AbiType::Ufixed(n, _) if *n <= 64 => ops(&["btoi"]),
+        AbiType::Byte => ops(&["btoi"]),
+        AbiType::Bool => ops(&["int 0", "getbit"]),
+        // the length prefix is implied by the argument's length
+        AbiType::String => ops(&["extract 2 0"]),
+        AbiType::DynamicArray(t) if **t == AbiType::Byte => ops(&["extract 2 0"]),
+        fixed if fixed.fixed_bytes_len().is_some() => encode(fixed),
+        _ => vec![],
+    }
+}
+
+/// Encodes `values` as an ARC-4 tuple of `types`, leaving the encoding on the
+/// stack. Consecutive bools are packed into single bytes, and dynamic values
+/// are appended to a tail referenced by offsets in the head, so the head and
+/// tail are built side by side on the stack.
+pub(crate) fn encode_tuple(
+    context: &CompilationContext,
+    types: &[AbiType],
+    values: &[Expr],
+) -> Result<Vec<String>, CompilationError> {
+    context.require_version("ABI encoding", 5)?;
+    let head_size = tuple_head_size(types);
+
+    let mut pieces = vec!["byte \"\"".to_string(), "byte \"\"".to_string()];
+    let mut i = 0;
+    while i < types.len() {
+        let bools = bool_run(&types[i..]).min(8);
+        if bools > 0 {
+            pieces.extend(["swap".to_string(), "int 0".to_string()]);
+            for (bit, value) in values[i..i + bools].iter().enumerate() {
+                pieces.extend([
+                    value.compile(context, &mut vec![])?,
+                    "int 0".to_string(),
+                    "!=".to_string(),
+                    format!("int {}", 0x80 >> bit),
+                    "*".to_string(),
+                    "+".to_string(),
+                ]);
+            }
+            pieces.extend(ops(&["itob", "extract 7 1", "concat", "swap"]));
+            i += bools;
+            continue;
+        }
+
+        let value = values[i].compile(context, &mut vec![])?;
+        if types[i].is_dynamic() {
+            // offset of this value: the head size plus the tail so far
+            pieces.extend(ops(&["dup", "len"]));
+            pieces.push(format!("int {head_size}"));
+            pieces.extend(ops(&[
+                "+",
+                "itob",
+                "extract 6 2",
+                "uncover 2",
+                "swap",
+                "concat",
+                "swap",
+            ]));
+            pieces.push(value);
+            pieces.extend(encode(&types[i]));
+            pieces.push("concat".to_string());
+        } else {
+            pieces.extend(["swap".to_string(), value.clone()]);
+            let literal = match &values[i] {
+                Expr::Primitive(Primitive::Byteslice(bytes)) => Some(bytes),
+                _ => None,
+            };
+            match (types[i].fixed_bytes_len(), literal) {
+                (Some(n), Some(bytes)) if bytes.len() == n => {}
+                (Some(_), Some(bytes)) => {
+                    return Err(CompilationError::AbiValueLength(
+                        types[i].to_string(),
+                        bytes.len(),
+                    ))
+                }
+                _ => pieces.extend(encode(&types[i])),
+            }
+            pieces.extend(ops(&["concat", "swap"]));
+        }
+        i += 1;
+    }
+    pieces.push("concat".to_string());
+    Ok(pieces)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{selector, AbiError, AbiType, AbiValue};
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn round_trip(abi_type: &str, value: AbiValue, encoded: &str) {
+        let abi_type = AbiType::from_str(abi_type).unwrap();
+        assert_eq!(abi_type.encode(&value).unwrap(), hex(encoded), "{abi_type}");
+        assert_eq!(abi_type.decode(&hex(encoded)).unwrap(), value, "{abi_type}");
+    }
+
+    #[test]
+    fn selectors() {
+        // from the ARC-4 specification
+        assert_eq!(
+            selector("add(uint64,uint64)uint128"),
+            [0x8a, 0xa3, 0xb6, 0x1f]
+        );
+    }
+
+    #[test]
+    fn types() {
+        for t in [
+            "uint8",
+            "ufixed128x10",
+            "byte[32]",
+            "bool[]",
+            "(uint64,(string,bool[3]))[]",
+            "()",
+        ] {
+            assert_eq!(AbiType::from_str(t).unwrap().to_string(), t);
+        }
+        assert_eq!(
+            AbiType::from_str("(uint64, byte[])").unwrap().to_string(),
+            "(uint64,byte[])"
+        );
+        for t in ["uint7", "uint1024", "ufixed64x0", "bytes", "uint64[x]"] {
+            assert!(AbiType::from_str(t).is_err(), "{t}");
+        }
+        assert_eq!(
+            AbiType::from_str("(uint16,bool,bool)")
+                .unwrap()
+                .static_size(),
+            Some(3)
+        );
+        assert!(AbiType::from_str("(uint16,string)").unwrap().is_dynamic());
+    }
+
+    #[test]
+    fn reference_vectors() {
+        // TestEncodeValid in go-algorand's data/abi/abi_encode_test.go, the
+        // reference implementation of the ARC-4 "Encoding Rules" section
+        let bools = |bits: &str| AbiValue::Array(bits.chars().map(|b| (b == '1').into()).collect());
+        // bool packing
+        round_trip("bool[5]", bools("10011"), "98");
+        round_trip("bool[11]", bools("10011010011"), "9a60");
+        round_trip("bool[]", bools("10011010011"), "000b9a60");
+        round_trip(
+            "(bool[2],bool[2])",
+            AbiValue::Tuple(vec![bools("11"), bools("11")]),
+            "c0c0",
+        );
+        // dynamic arrays in the tail, addressed by head offsets
+        round_trip(
+            "(bool[2],bool[])",
+            AbiValue::Tuple(vec![bools("11"), bools("11")]),
+            "c000030002c0",
+        );
+        round_trip(
+            "(bool[],bool[])",
+            AbiValue::Tuple(vec![bools(""), bools("")]),
+            "0004000600000000",
+        );
+        round_trip(
+            "(string,bool,bool,bool,bool,string)",
+            AbiValue::Tuple(vec![
+                "ABC".into(),
+                true.into(),
+                false.into(),
+                true.into(),
+                false.into(),
+                "DEF".into(),
+            ]),
+            "0005a0000a00034142430003444546",
+        );
+        round_trip("()", AbiValue::Tuple(vec![]), "");
+    }
+
+    #[test]
+    fn encoding() {
+        round_trip("uint64", 1u64.into(), "0000000000000001");
+        round_trip("uint8", 255u64.into(), "ff");
+        round_trip("ufixed32x2", 12345u64.into(), "00003039");
+        round_trip("bool", true.into(), "80");
+        round_trip("string", "asdf".into(), "000461736466");
+        round_trip("byte[3]", AbiValue::bytes(b"abc"), "616263");
+        round_trip("byte[]", AbiValue::bytes(b"ab"), "00026162");
+        round_trip(
+            "bool[3]",
+            AbiValue::Array(vec![true.into(), true.into(), false.into()]),
+            "c0",
+        );
+        round_trip(
+            "bool[]",
+            AbiValue::Array(vec![true.into(), false.into(), true.into()]),
+            "0003a0",
+        );
+        round_trip(
+            "uint64[]",
+            AbiValue::Array(vec![1u64.into(), 2u64.into()]),
+            "000200000000000000010000000000000002",
+        );
+        round_trip(
+            "(uint16,uint16)",
+            AbiValue::Tuple(vec![1u64.into(), 2u64.into()]),
+            "00010002",
+        );
+        round_trip(
+            "(string,string)",
+            AbiValue::Tuple(vec!["ABC".into(), "DEF".into()]),
+            "0004000900034142430003444546",
+        );
+        round_trip(
+            "(bool,bool,uint64,bool)",
+            AbiValue::Tuple(vec![true.into(), false.into(), 5u64.into(), true.into()]),
+            "80000000000000000580",
+        );
+        round_trip(
+            "(uint16,string[],bool)",
+            AbiValue::Tuple(vec![
+                7u64.into(),
+                AbiValue::Array(vec!["a".into(), "bc".into()]),
+                false.into(),
+            ]),
+            "000700050000020004000700016100026263",
+        );
+        round_trip(
+            "(uint8,(string,bool))",
+            AbiValue::Tuple(vec![
+                1u64.into(),
+                AbiValue::Tuple(vec!["abc".into(), true.into()]),
+            ]),
+            "0100030003800003616263",
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            AbiType::Uint(8).encode(&256u64.into()),
+            Err(AbiError::TypeMismatch(..))
+        ));
+        assert!(AbiType::String.decode(&hex("0005616263")).is_err());
+        assert!(AbiType::from_str("uint64[2]")
+            .unwrap()
+            .decode(&hex("0000000000000001"))
+            .is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use std::{fmt::Display, str::FromStr};
+
+use sha2::{Digest, Sha512_256};
+use thiserror::Error;
+
+use crate::abi::AbiValue;
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const CHECKSUM_LENGTH: usize = 4;
+/// Length of the base32 encoding of a public key followed by its checksum
+pub const ADDRESS_LENGTH: usize = 58;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum AddressError {
+    #[error("Address must be 58 characters long, but has {0}")]
+    InvalidLength(usize),
+    #[error("Invalid base32 character {0:?}")]
+    InvalidCharacter(char),
+    #[error("Address checksum does not match")]
+    InvalidChecksum,
+}
+
+/// An Algorand account address: a 32-byte public key, or the hash standing in
+/// for one, written in base32 followed by a 4-byte checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address(pub [u8; 32]);
+
+impl Address {
+    /// Escrow address of a logic signature, from its assembled bytecode.
+    pub fn from_program(bytecode: &[u8]) -> Self {
+        Self::hash(&[b"Program", bytecode])
+    }
+
+    /// Account address of an application.
+    pub fn from_app_id(app_id: u64) -> Self {
+        Self::hash(&[b"appID", &app_id.to_be_bytes()])
+    }
+
+    fn hash(parts: &[&[u8]]) -> Self {
+        let mut hasher = Sha512_256::new();
+        parts.iter().for_each(|part| hasher.update(part));
+        Address(hasher.finalize().into())
+    }
+
+    fn checksum(&self) -> [u8; CHECKSUM_LENGTH] {
+        let hash = Sha512_256::digest(self.0);
+        hash[hash.len() - CHECKSUM_LENGTH..].try_into().unwrap()
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut bytes = self.0.to_vec();
+        bytes.extend(self.checksum());
+        write!(f, "{}", base32_encode(&bytes))
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != ADDRESS_LENGTH {
+            return Err(AddressError::InvalidLength(s.len()));
+        }
+        let bytes = base32_decode(s)?;
+        let (key, checksum) = bytes.split_at(32);
+        let address = Address(key.try_into().unwrap());
+        if address.checksum() != checksum {
+            return Err(AddressError::InvalidChecksum);
+        }
+        Ok(address)
+    }
+}
+
+impl From<Address> for AbiValue {
+    fn from(address: Address) -> Self {
+        AbiValue::Address(address.0)
+    }
+}
+
+/// Unpadded RFC 4648 base32.
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    encoded
+}
+
+pub fn base32_decode(s: &str) -> Result<Vec<u8>, AddressError> {
+    let mut bytes = vec![];
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in s.chars() {
+        let value = ALPHABET
+            .iter()
+            .position(|a| *a as char == c)
+            .ok_or(AddressError::InvalidCharacter(c))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Address, AddressError};
+
+    const ZERO_ADDRESS: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAY5HFKQ";
+
+    #[test]
+    fn test() {
+        let zero = Address([0; 32]);
+        println!("{zero}");
+        assert_eq!(zero.to_string(), ZERO_ADDRESS);
+        assert_eq!(ZERO_ADDRESS.parse(), Ok(zero));
+
+        let app = Address::from_app_id(1);
+        assert_eq!(app.to_string().parse(), Ok(app));
+        assert_ne!(app, Address::from_app_id(2));
+        // `#pragma version 8; int 1`
+        let lsig = Address::from_program(&[0x08, 0x81, 0x01]);
+        assert_eq!(lsig.to_string().len(), 58);
+
+        let mut corrupted = ZERO_ADDRESS.to_string();
+        corrupted.replace_range(..1, "B");
+        assert_eq!(
+            corrupted.parse::<Address>(),
+            Err(AddressError::InvalidChecksum)
+        );
+        assert_eq!(
+            "AAAA".parse::<Address>(),
+            Err(AddressError::InvalidLength(4))
+        );
+        assert_eq!(
+            ZERO_ADDRESS.replace('Y', "1").parse::<Address>(),
+            Err(AddressError::InvalidCharacter('1'))
+        );
+    }
+}
@@ -0,0 +1,702 @@
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compilation_error::CompilationError,
+    contract::{CompiledContract, Contract},
+    description::{ContractDescription, EventDescription, MethodDescription},
+    expression::constant::OnComplete,
+    method::bare_actions,
+    struct_def::{StateSchema, StructDef},
+    template::constant_blocks,
+    typing::TypePrimitive,
+};
+
+/// ARC-56 application specification, describing everything needed to deploy
+/// and call the contract.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSpec {
+    pub arcs: Vec<u32>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    pub structs: BTreeMap<String, Vec<StructField>>,
+    pub methods: Vec<Method>,
+    pub state: State,
+    pub bare_actions: Actions,
+    pub source_info: ProgramPair<ProgramSourceInfo>,
+    /// Base64 encoded TEAL
+    pub source: ProgramPair<String>,
+    /// Application ids keyed by network genesis hash, filled in once deployed
+    pub networks: BTreeMap<String, Network>,
+    pub events: Vec<EventDescription>,
+    /// Template variables whose `TMPL_` placeholders must be substituted
+    /// before deploying
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub template_variables: BTreeMap<String, TemplateVariable>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Method {
+    #[serde(flatten)]
+    pub description: MethodDescription,
+    pub actions: Actions,
+    pub readonly: bool,
+}
+
+/// On-completion actions allowed when creating the application and when
+/// calling it once created.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Actions {
+    pub create: Vec<OnComplete>,
+    pub call: Vec<OnComplete>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    pub schema: StateSchemas,
+    pub keys: Storage<StorageKey>,
+    pub maps: Storage<StorageMap>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSchemas {
+    pub global: SchemaCounts,
+    pub local: SchemaCounts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SchemaCounts {
+    pub ints: u64,
+    pub bytes: u64,
+}
+
+impl From<StateSchema> for SchemaCounts {
+    fn from(schema: StateSchema) -> Self {
+        SchemaCounts {
+            ints: schema.num_uints,
+            bytes: schema.num_byte_slices,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Storage<T> {
+    pub global: BTreeMap<String, T>,
+    pub local: BTreeMap<String, T>,
+    #[serde(rename = "box")]
+    pub boxes: BTreeMap<String, T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageKey {
+    pub key_type: String,
+    pub value_type: String,
+    /// Base64 encoded key
+    pub key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMap {
+    pub key_type: String,
+    pub value_type: String,
+    /// Base64 encoded prefix of the keys
+    pub prefix: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgramPair<T> {
+    pub approval: T,
+    pub clear: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramSourceInfo {
+    pub source_info: Vec<SourceInfo>,
+    pub pc_offset_method: String,
+}
+
+/// Error message reported when the program fails at a TEAL line. Program
+/// counters are only known once the TEAL is assembled, so `pc` is empty until
+/// filled in by [`AppSpec::with_source_maps`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceInfo {
+    pub pc: Vec<u64>,
+    pub error_message: String,
+    /// 1-based TEAL line
+    pub teal: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateVariable {
+    #[serde(rename = "type")]
+    pub template_type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Network {
+    #[serde(rename = "appID")]
+    pub app_id: u64,
+}
+
+fn avm_type(primitive: &TypePrimitive) -> String {
+    match primitive {
+        TypePrimitive::UInt64 => "AVMUint64",
+        _ => "AVMBytes",
+    }
+    .to_string()
+}
+
+/// Declared keys of a schema. Packed schemas are stored under a single
+/// byteslice key.
+fn storage_keys(schema: &StructDef) -> Result<BTreeMap<String, StorageKey>, CompilationError> {
+    let key = |name: &str, primitive: &TypePrimitive| StorageKey {
+        key_type: "AVMString".to_string(),
+        value_type: avm_type(primitive),
+        key: STANDARD.encode(name),
+    };
+    if let Some(layout) = schema.packed_layout()? {
+        return Ok(BTreeMap::from([(
+            layout.key.clone(),
+            key(&layout.key, &TypePrimitive::Byteslice),
+        )]));
+    }
+    Ok(schema
+        .fields
+        .iter()
+        .map(|(name, primitive)| (name.to_string(), key(name, primitive)))
+        .collect())
+}
+
+fn storage_maps(schema: &StructDef) -> BTreeMap<String, StorageMap> {
+    schema
+        .maps
+        .iter()
+        .map(|(name, (key, value))| {
+            (
+                name.to_string(),
+                StorageMap {
+                    key_type: avm_type(key),
+                    value_type: avm_type(value),
+                    prefix: STANDARD.encode(name),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Error messages attached to failing ops as comments, e.g.
+/// `err // unknown method selector`. Program counters of templated programs
+/// are counted from the end of their constant blocks, whose size depends on
+/// the substituted values.
+fn source_info(teal: &str, templated: bool) -> ProgramSourceInfo {
+    let source_info = teal
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (op, message) = line.split_once(" // ")?;
+            matches!(op, "err" | "assert").then(|| SourceInfo {
+                pc: vec![],
+                error_message: message.to_string(),
+                teal: i + 1,
+            })
+        })
+        .collect();
+    ProgramSourceInfo {
+        source_info,
+        pc_offset_method: if templated { "cblocks" } else { "none" }.to_string(),
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Decodes a segment of base64 VLQ encoded, signed values.
+fn decode_vlq(segment: &str) -> Result<Vec<i64>, CompilationError> {
+    let mut values = vec![];
+    let (mut value, mut shift) = (0i64, 0);
+    for c in segment.bytes() {
+        let digit = BASE64_ALPHABET
+            .iter()
+            .position(|a| *a == c)
+            .ok_or(CompilationError::MalformedSourceMap("invalid base64 digit"))?
+            as i64;
+        if shift > 55 {
+            return Err(CompilationError::MalformedSourceMap("value is too long"));
+        }
+        value |= (digit & 31) << shift;
+        if digit & 32 != 0 {
+            shift += 5;
+            continue;
+        }
+        values.push(if value & 1 == 1 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        (value, shift) = (0, 0);
+    }
+    Ok(values)
+}
+
+/// Program counters of the ops on each 0-based TEAL line, from the `mappings`
+/// of a source map returned by algod, which hold one segment per program
+/// counter, empty unless an op starts there.
+fn line_pcs(mappings: &str) -> Result<BTreeMap<usize, Vec<u64>>, CompilationError> {
+    let mut pcs = BTreeMap::<usize, Vec<u64>>::new();
+    let mut line = 0;
+    for (pc, segment) in mappings.split(';').enumerate() {
+        if segment.is_empty() {
+            continue;
+        }
+        line += decode_vlq(segment)?
+            .get(2)
+            .ok_or(CompilationError::MalformedSourceMap("segment has no line"))?;
+        let line = usize::try_from(line)
+            .map_err(|_| CompilationError::MalformedSourceMap("negative line"))?;
+        pcs.entry(line).or_default().push(pc as u64);
+    }
+    Ok(pcs)
+}
+
+impl ProgramSourceInfo {
+    fn fill_pcs(&mut self, mappings: &str) -> Result<(), CompilationError> {
+        let pcs = line_pcs(mappings)?;
+        // the code of a templated program starts after `#pragma` and the
+        // constant blocks
+        let code_line = constant_blocks(&[]).lines().count() + 1;
+        let offset = match self.pc_offset_method.as_str() {
+            "cblocks" => pcs
+                .range(code_line..)
+                .flat_map(|(_, pcs)| pcs.first())
+                .min()
+                .copied()
+                .unwrap_or_default(),
+            _ => 0,
+        };
+        for info in &mut self.source_info {
+            info.pc = pcs
+                .get(&(info.teal - 1))
+                .map(|pcs| pcs.iter().map(|pc| pc - offset).collect())
+                .unwrap_or_default();
+        }
+        Ok(())
+    }
+}
+
+impl AppSpec {
+    /// Compiles `contract` and describes it.
+    pub fn new(name: &str, contract: &Contract) -> Result<Self, CompilationError> {
+        let compiled = contract.compile()?;
+        let description = ContractDescription::new(name, &compiled);
+        let CompiledContract {
+            approval,
+            clear,
+            global_schema,
+            local_schema,
+            methods,
+            templates,
+            ..
+        } = compiled;
+        let templated = !templates.is_empty();
+        Ok(AppSpec {
+            arcs: vec![4, 56],
+            name: description.name,
+            desc: description.desc,
+            structs: BTreeMap::new(),
+            methods: description
+                .methods
+                .into_iter()
+                .zip(methods)
+                .map(|(description, method)| {
+                    let (create, call) = method.actions();
+                    Method {
+                        description,
+                        actions: Actions { create, call },
+                        readonly: method.readonly,
+                    }
+                })
+                .collect(),
+            state: State {
+                schema: StateSchemas {
+                    global: global_schema.into(),
+                    local: local_schema.into(),
+                },
+                keys: Storage {
+                    global: storage_keys(&contract.schema_global)?,
+                    local: storage_keys(&contract.schema_local)?,
+                    boxes: storage_keys(&contract.schema_box)?,
+                },
+                maps: Storage {
+                    global: BTreeMap::new(),
+                    local: BTreeMap::new(),
+                    boxes: storage_maps(&contract.schema_box),
+                },
+            },
+            bare_actions: bare_actions(&contract.txn_approval.methods),
+            source_info: ProgramPair {
+                approval: source_info(&approval, templated),
+                clear: source_info(&clear, templated),
+            },
+            source: ProgramPair {
+                approval: STANDARD.encode(approval),
+                clear: STANDARD.encode(clear),
+            },
+            networks: BTreeMap::new(),
+            events: description.events,
+            template_variables: templates
+                .iter()
+                .map(|t| {
+                    let template_type = avm_type(&t.primitive);
+                    (t.name.clone(), TemplateVariable { template_type })
+                })
+                .collect(),
+        })
+    }
+
+    /// Fills in the program counters of the error messages from the
+    /// `mappings` of the source maps algod returns when compiling the
+    /// approval and clear programs (`/v2/teal/compile?sourcemap=true`).
+    /// Templated programs must be compiled with values substituted by
+    /// [`crate::template::substitute_teal`], which keeps TEAL lines in place.
+    pub fn with_source_maps(
+        mut self,
+        approval: &str,
+        clear: &str,
+    ) -> Result<Self, CompilationError> {
+        self.source_info.approval.fill_pcs(approval)?;
+        self.source_info.clear.fill_pcs(clear)?;
+        Ok(self)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// The equivalent ARC-32 application specification, for tools that do not
+    /// support ARC-56 yet.
+    pub fn to_arc32(&self) -> Arc32Spec {
+        let declared = |keys: &BTreeMap<String, StorageKey>| Arc32Schema {
+            declared: keys
+                .iter()
+                .map(|(name, key)| {
+                    (
+                        name.clone(),
+                        Arc32Key {
+                            key_type: match key.value_type.as_str() {
+                                "AVMUint64" => "uint64",
+                                _ => "bytes",
+                            }
+                            .to_string(),
+                            key: name.clone(),
+                            descr: String::new(),
+                        },
+                    )
+                })
+                .collect(),
+            reserved: BTreeMap::new(),
+        };
+        let counts = |counts: &SchemaCounts| Arc32Counts {
+            num_uints: counts.ints,
+            num_byte_slices: counts.bytes,
+        };
+        Arc32Spec {
+            hints: self
+                .methods
+                .iter()
+                .map(|method| {
+                    (
+                        method.description.signature(),
+                        Arc32Hint {
+                            call_config: call_config(&method.actions),
+                        },
+                    )
+                })
+                .collect(),
+            source: self.source.clone(),
+            state: Arc32State {
+                global: counts(&self.state.schema.global),
+                local: counts(&self.state.schema.local),
+            },
+            schema: Arc32Schemas {
+                global: declared(&self.state.keys.global),
+                local: declared(&self.state.keys.local),
+            },
+            contract: ContractDescription {
+                name: self.name.clone(),
+                desc: self.desc.clone(),
+                networks: BTreeMap::new(),
+                methods: self
+                    .methods
+                    .iter()
+                    .map(|method| method.description.clone())
+                    .collect(),
+                events: self.events.clone(),
+            },
+            bare_call_config: call_config(&self.bare_actions),
+        }
+    }
+}
+
+/// ARC-32 application specification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arc32Spec {
+    /// Keyed by method signature
+    pub hints: BTreeMap<String, Arc32Hint>,
+    pub source: ProgramPair<String>,
+    pub state: Arc32State,
+    pub schema: Arc32Schemas,
+    pub contract: ContractDescription,
+    pub bare_call_config: BTreeMap<String, CallConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arc32Hint {
+    pub call_config: BTreeMap<String, CallConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CallConfig {
+    Call,
+    Create,
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arc32State {
+    pub global: Arc32Counts,
+    pub local: Arc32Counts,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arc32Counts {
+    pub num_uints: u64,
+    pub num_byte_slices: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arc32Schemas {
+    pub global: Arc32Schema,
+    pub local: Arc32Schema,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arc32Schema {
+    pub declared: BTreeMap<String, Arc32Key>,
+    pub reserved: BTreeMap<String, Arc32Key>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arc32Key {
+    #[serde(rename = "type")]
+    pub key_type: String,
+    pub key: String,
+    pub descr: String,
+}
+
+impl Arc32Spec {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// Merges allowed create and call actions into ARC-32 call configs, keyed by
+/// the snake case on-completion name.
+fn call_config(actions: &Actions) -> BTreeMap<String, CallConfig> {
+    let mut config = BTreeMap::new();
+    for (on_complete, call_config) in actions
+        .create
+        .iter()
+        .map(|oc| (oc, CallConfig::Create))
+        .chain(actions.call.iter().map(|oc| (oc, CallConfig::Call)))
+    {
+        let key = match on_complete {
+            OnComplete::NoOp => "no_op",
+            OnComplete::OptIn => "opt_in",
+            OnComplete::CloseOut => "close_out",
+            OnComplete::ClearState => "clear_state",
+            OnComplete::UpdateApplication => "update_application",
+            OnComplete::DeleteApplication => "delete_application",
+        };
+        config
+            .entry(key.to_string())
+            .and_modify(|c| *c = CallConfig::All)
+            .or_insert(call_config);
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    use crate::{
+        abi::AbiType,
+        contract::Contract,
+        expression::{constant::OnComplete, primitive::Primitive, Expr},
+        int,
+        method::AbiMethod,
+        program::Program,
+        struct_def::StructDef,
+        typing::TypePrimitive,
+    };
+
+    use super::{line_pcs, AppSpec, CallConfig, ProgramSourceInfo, SourceInfo};
+
+    fn contract() -> Contract<'static> {
+        Contract {
+            doc: None,
+            schema_global: StructDef {
+                fields: HashMap::from([("counter", TypePrimitive::UInt64)]),
+                ..Default::default()
+            },
+            schema_local: StructDef::default(),
+            schema_box: StructDef {
+                maps: HashMap::from([(
+                    "balances",
+                    (TypePrimitive::Byteslice, TypePrimitive::UInt64),
+                )]),
+                ..Default::default()
+            },
+            events: vec![],
+            txn_approval: Program {
+                body: int!(1),
+                methods: vec![AbiMethod {
+                    name: "ping".to_string(),
+                    args: vec![("n".to_string(), AbiType::Uint(8))],
+                    returns: Some(AbiType::Uint(8)),
+                    body: int!(1),
+                    doc: None,
+                    create: false,
+                    on_complete: vec![],
+                    creator_only: false,
+                    readonly: false,
+                }],
+                ..Default::default()
+            },
+            txn_clear: Program::default(),
+            templates: vec![],
+            lsigs: vec![],
+        }
+    }
+
+    #[test]
+    fn arc56() {
+        let spec = AppSpec::new("Pinger", &contract()).unwrap();
+        let json = spec.to_json();
+        println!("{json}");
+        assert_eq!(spec.state.schema.global.ints, 1);
+        assert_eq!(spec.state.keys.global["counter"].key, "Y291bnRlcg==");
+        assert_eq!(spec.state.keys.global["counter"].value_type, "AVMUint64");
+        assert_eq!(spec.state.maps.boxes["balances"].key_type, "AVMBytes");
+        assert_eq!(spec.methods[0].actions.call, vec![OnComplete::NoOp]);
+        assert!(json.contains("\"bareActions\""));
+        assert!(json.contains("\"call\": [\n          \"NoOp\"\n        ]"));
+
+        let messages = spec
+            .source_info
+            .approval
+            .source_info
+            .iter()
+            .map(|info| info.error_message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "unknown method selector",
+                "OnCompletion not allowed",
+                "cannot create",
+                "uint8 overflow",
+                "OnCompletion not allowed"
+            ]
+        );
+        assert!(spec.source_info.clear.source_info.is_empty());
+
+        assert_eq!(AppSpec::from_json(&json).unwrap(), spec);
+
+        let mut bare = contract();
+        bare.txn_approval.methods.clear();
+        let spec = AppSpec::new("Bare", &bare).unwrap();
+        assert_eq!(spec.bare_actions.call.len(), 5);
+        assert!(spec.bare_actions.create.contains(&OnComplete::OptIn));
+    }
+
+    #[test]
+    fn source_maps() {
+        let pcs = line_pcs("AAAA;;AAgBA;AADA").unwrap();
+        assert_eq!(
+            pcs,
+            BTreeMap::from([(0, vec![0]), (15, vec![3]), (16, vec![2])])
+        );
+        assert!(line_pcs("AA!A").is_err());
+
+        // one single-byte op per line
+        let mappings = |source: &str| {
+            let lines = String::from_utf8(STANDARD.decode(source).unwrap())
+                .unwrap()
+                .lines()
+                .count();
+            std::iter::once("AAAA")
+                .chain(std::iter::repeat_n("AACA", lines - 1))
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        let spec = AppSpec::new("Pinger", &contract()).unwrap();
+        let (approval, clear) = (
+            mappings(&spec.source.approval),
+            mappings(&spec.source.clear),
+        );
+        let spec = spec.with_source_maps(&approval, &clear).unwrap();
+        println!("{:?}", spec.source_info.approval);
+        for info in &spec.source_info.approval.source_info {
+            assert_eq!(info.pc, [info.teal as u64 - 1]);
+        }
+
+        let mut templated = ProgramSourceInfo {
+            source_info: vec![SourceInfo {
+                pc: vec![],
+                error_message: "unknown method selector".to_string(),
+                teal: 5,
+            }],
+            pc_offset_method: "cblocks".to_string(),
+        };
+        // intcblock and bytecblock take 2 and 3 bytes
+        templated.fill_pcs("AAAA;AACA;;AACA;;;AACA;AACA").unwrap();
+        assert_eq!(templated.source_info[0].pc, [1]);
+    }
+
+    #[test]
+    fn arc32() {
+        let spec = AppSpec::new("Pinger", &contract()).unwrap().to_arc32();
+        println!("{}", spec.to_json());
+        assert_eq!(
+            spec.hints["ping(uint8)uint8"].call_config["no_op"],
+            CallConfig::Call
+        );
+        assert_eq!(spec.bare_call_config["no_op"], CallConfig::All);
+        assert_eq!(spec.state.global.num_uints, 1);
+        assert_eq!(spec.schema.global.declared["counter"].key_type, "uint64");
+        assert_eq!(spec.contract.methods[0].name, "ping");
+    }
+}
@@ -0,0 +1,539 @@
+//! Typed Rust clients for compiled contracts. [`generate`] turns an app spec
+//! into a Rust module, e.g. from a `build.rs`:
+//!
+//! ```ignore
+//! let contract = parser::parse(&source)?;
+//! let spec = AppSpec::new("Calculator", &contract)?;
+//! let out = PathBuf::from(env::var("OUT_DIR")?).join("calculator.rs");
+//! fs::write(out, rusteal_ast::client::generate(&spec))?;
+//! ```
+//!
+//! and `include!(concat!(env!("OUT_DIR"), "/calculator.rs"));` in the crate.
+//! The generated code calls into the helpers below.
+
+use std::{collections::HashMap, fmt::Write, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{
+    abi::{AbiError, AbiType, AbiValue},
+    app_spec::{AppSpec, Method, StorageKey, StorageMap},
+    expression::constant::OnComplete,
+    method::RETURN_PREFIX,
+};
+
+/// Arguments of an application call transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppCall {
+    /// 0 when creating the application
+    pub app_id: u64,
+    pub on_complete: OnComplete,
+    pub args: Vec<Vec<u8>>,
+}
+
+/// A value of global or local state, as returned by algod.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateValue {
+    Uint(u64),
+    Bytes(Vec<u8>),
+}
+
+impl StateValue {
+    pub fn as_uint(&self) -> Option<u64> {
+        match self {
+            StateValue::Uint(n) => Some(*n),
+            StateValue::Bytes(_) => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            StateValue::Uint(_) => None,
+            StateValue::Bytes(bytes) => Some(bytes.clone()),
+        }
+    }
+}
+
+/// Global state, or the local state of an account, keyed by state key.
+pub type State = HashMap<Vec<u8>, StateValue>;
+
+/// Encodes the arguments of a method call, given as ABI types and values.
+pub fn method_call(
+    app_id: u64,
+    on_complete: OnComplete,
+    selector: [u8; 4],
+    args: Vec<(&str, AbiValue)>,
+) -> Result<AppCall, AbiError> {
+    let mut encoded = vec![selector.to_vec()];
+    for (abi_type, value) in args {
+        encoded.push(AbiType::from_str(abi_type)?.encode(&value)?);
+    }
+    Ok(AppCall {
+        app_id,
+        on_complete,
+        args: encoded,
+    })
+}
+
+/// Decodes a method's return value from the last log of the call.
+pub fn decode_return<T: TryFrom<AbiValue>>(abi_type: &str, log: &[u8]) -> Result<T, AbiError> {
+    let abi_type = AbiType::from_str(abi_type)?;
+    let malformed = || AbiError::Malformed(abi_type.clone());
+    let encoded = log
+        .strip_prefix(RETURN_PREFIX.as_slice())
+        .ok_or_else(malformed)?;
+    abi_type
+        .decode(encoded)?
+        .try_into()
+        .map_err(|_| malformed())
+}
+
+/// Rust types that values of `abi_type` are passed as and returned as.
+fn rust_types(abi_type: &AbiType) -> (&'static str, &'static str) {
+    match abi_type {
+        AbiType::Uint(n) | AbiType::Ufixed(n, _) if *n <= 64 => ("u64", "u64"),
+        AbiType::Uint(n) | AbiType::Ufixed(n, _) if *n <= 128 => ("u128", "u128"),
+        AbiType::Bool => ("bool", "bool"),
+        AbiType::Byte => ("u8", "u8"),
+        AbiType::Address => ("[u8; 32]", "[u8; 32]"),
+        AbiType::String => ("&str", "String"),
+        AbiType::StaticArray(t, _) | AbiType::DynamicArray(t) if **t == AbiType::Byte => {
+            ("&[u8]", "Vec<u8>")
+        }
+        _ => ("AbiValue", "AbiValue"),
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 && !snake.ends_with('_') {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+/// Rust keywords, which are escaped in generated identifiers
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Snake case Rust identifier for `name`, as a raw identifier if it is a
+/// keyword. `self`, `super` and `crate` cannot be raw, so they get a `_`.
+fn identifier(name: &str) -> String {
+    let snake = snake_case(name);
+    match snake.as_str() {
+        "self" | "super" | "crate" => format!("{snake}_"),
+        keyword if KEYWORDS.contains(&keyword) => format!("r#{snake}"),
+        _ => snake,
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| c == '_' || c == '-' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .unwrap()
+                .to_uppercase()
+                .chain(chars)
+                .collect::<String>()
+        })
+        .collect()
+}
+
+fn doc_lines(out: &mut String, doc: Option<&str>) {
+    for line in doc.unwrap_or_default().lines() {
+        writeln!(out, "    /// {line}").unwrap();
+    }
+}
+
+/// Rust expression for a state key or box name prefix, given in base64.
+fn key_literal(key: &str) -> String {
+    let bytes = STANDARD.decode(key).unwrap_or_default();
+    match String::from_utf8(bytes.clone()) {
+        Ok(s) => format!("{s:?}.as_bytes()"),
+        Err(_) => format!("{bytes:?}.as_slice()"),
+    }
+}
+
+fn generate_method(out: &mut String, method: &Method) {
+    let description = &method.description;
+    let name = snake_case(&description.name);
+    let (app_id, allowed) = match method.actions.call.is_empty() {
+        false => ("self.app_id", &method.actions.call),
+        true => ("0", &method.actions.create),
+    };
+    // callers choose among several allowed actions
+    let (on_complete, on_complete_param) = match allowed.as_slice() {
+        [] => return,
+        [on_complete] => (format!("OnComplete::{on_complete:?}"), ""),
+        _ => ("on_complete".to_string(), ", on_complete: OnComplete"),
+    };
+    let params = description
+        .args
+        .iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            let arg_name = arg.name.as_deref().map_or(format!("arg{i}"), identifier);
+            (
+                arg_name,
+                arg.abi_type.to_string(),
+                rust_types(&arg.abi_type).0,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    doc_lines(out, description.desc.as_deref());
+    if description.desc.is_some() {
+        writeln!(out, "    ///").unwrap();
+    }
+    writeln!(out, "    /// Calls `{}`.", description.signature()).unwrap();
+    if method.readonly {
+        writeln!(out, "    /// Read-only, so it may be simulated instead.").unwrap();
+    }
+    let signature = params
+        .iter()
+        .map(|(arg_name, _, rust_type)| format!(", {arg_name}: {rust_type}"))
+        .collect::<String>();
+    writeln!(
+        out,
+        "    pub fn {}(&self{on_complete_param}{signature}) -> Result<AppCall, AbiError> {{",
+        identifier(&description.name)
+    )
+    .unwrap();
+    writeln!(out, "        client::method_call(").unwrap();
+    writeln!(out, "            {app_id},").unwrap();
+    writeln!(out, "            {on_complete},").unwrap();
+    writeln!(out, "            {:?},", description.selector()).unwrap();
+    writeln!(out, "            vec![").unwrap();
+    for (arg_name, abi_type, _) in &params {
+        writeln!(
+            out,
+            "                ({abi_type:?}, AbiValue::from({arg_name})),"
+        )
+        .unwrap();
+    }
+    writeln!(out, "            ],").unwrap();
+    writeln!(out, "        )").unwrap();
+    writeln!(out, "    }}").unwrap();
+
+    if let Some(returns) = &description.returns.abi_type {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "    /// Decodes the value returned by `{}` from the last log of the call.",
+            description.name
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    pub fn {name}_return(log: &[u8]) -> Result<{}, AbiError> {{",
+            rust_types(returns).1
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "        client::decode_return({:?}, log)",
+            returns.to_string()
+        )
+        .unwrap();
+        writeln!(out, "    }}").unwrap();
+    }
+    writeln!(out).unwrap();
+}
+
+fn generate_key(out: &mut String, scope: &str, name: &str, key: &StorageKey) {
+    let (rust_type, conversion) = match key.value_type.as_str() {
+        "AVMUint64" => ("u64", "as_uint"),
+        _ => ("Vec<u8>", "as_bytes"),
+    };
+    writeln!(out, "    /// Value of {scope} `{name}`.").unwrap();
+    writeln!(
+        out,
+        "    pub fn {scope}_{}(state: &State) -> Option<{rust_type}> {{",
+        snake_case(name)
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "        state.get({})?.{conversion}()",
+        key_literal(&key.key)
+    )
+    .unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out).unwrap();
+}
+
+fn generate_box_key(out: &mut String, name: &str, key: &StorageKey) {
+    writeln!(out, "    /// Name of box `{name}`.").unwrap();
+    writeln!(out, "    pub fn {}_box() -> Vec<u8> {{", snake_case(name)).unwrap();
+    writeln!(out, "        {}.to_vec()", key_literal(&key.key)).unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out).unwrap();
+}
+
+fn generate_box_map(out: &mut String, name: &str, map: &StorageMap) {
+    let (rust_type, key_bytes) = match map.key_type.as_str() {
+        "AVMUint64" => ("u64", "key.to_be_bytes().as_slice()"),
+        _ => ("&[u8]", "key"),
+    };
+    writeln!(out, "    /// Name of the box holding `{name}[key]`.").unwrap();
+    writeln!(
+        out,
+        "    pub fn {}_box(key: {rust_type}) -> Vec<u8> {{",
+        snake_case(name)
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "        [{}, {key_bytes}].concat()",
+        key_literal(&map.prefix)
+    )
+    .unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out).unwrap();
+}
+
+/// Generates a Rust module with a struct for the contract described by
+/// `spec`, with typed methods building its calls and decoding their return
+/// values, accessors for its declared state and constants for its programs
+/// and state schema.
+pub fn generate(spec: &AppSpec) -> String {
+    let struct_name = pascal_case(&spec.name);
+    let program = |source: &str| {
+        String::from_utf8(STANDARD.decode(source).unwrap_or_default()).unwrap_or_default()
+    };
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated from the `{}` app spec. Do not edit.",
+        spec.name
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "#[allow(unused_imports)]").unwrap();
+    writeln!(out, "use rusteal_ast::{{").unwrap();
+    writeln!(out, "    abi::{{AbiError, AbiValue}},").unwrap();
+    writeln!(out, "    client::{{self, AppCall, State}},").unwrap();
+    writeln!(out, "    expression::constant::OnComplete,").unwrap();
+    writeln!(out, "}};").unwrap();
+    writeln!(out).unwrap();
+    if let Some(desc) = &spec.desc {
+        for line in desc.lines() {
+            writeln!(out, "/// {line}").unwrap();
+        }
+    }
+    writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq)]").unwrap();
+    writeln!(out, "pub struct {struct_name} {{").unwrap();
+    writeln!(out, "    pub app_id: u64,").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "impl {struct_name} {{").unwrap();
+    writeln!(
+        out,
+        "    pub const APPROVAL_PROGRAM: &'static str = {:?};",
+        program(&spec.source.approval)
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    pub const CLEAR_PROGRAM: &'static str = {:?};",
+        program(&spec.source.clear)
+    )
+    .unwrap();
+    let schema = &spec.state.schema;
+    for (name, value) in [
+        ("GLOBAL_NUM_UINTS", schema.global.ints),
+        ("GLOBAL_NUM_BYTE_SLICES", schema.global.bytes),
+        ("LOCAL_NUM_UINTS", schema.local.ints),
+        ("LOCAL_NUM_BYTE_SLICES", schema.local.bytes),
+    ] {
+        writeln!(out, "    pub const {name}: u64 = {value};").unwrap();
+    }
+    writeln!(out).unwrap();
+    writeln!(out, "    pub fn new(app_id: u64) -> Self {{").unwrap();
+    writeln!(out, "        {struct_name} {{ app_id }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out).unwrap();
+    for method in &spec.methods {
+        generate_method(&mut out, method);
+    }
+    for (name, key) in &spec.state.keys.global {
+        generate_key(&mut out, "global", name, key);
+    }
+    for (name, key) in &spec.state.keys.local {
+        generate_key(&mut out, "local", name, key);
+    }
+    for (name, key) in &spec.state.keys.boxes {
+        generate_box_key(&mut out, name, key);
+    }
+    for (name, map) in &spec.state.maps.boxes {
+        generate_box_map(&mut out, name, map);
+    }
+    // no blank line before the closing brace
+    out.pop();
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        abi::{AbiType, AbiValue},
+        app_spec::AppSpec,
+        contract::Contract,
+        expression::{constant::OnComplete, primitive::Primitive, Expr},
+        int,
+        method::{AbiMethod, RETURN_PREFIX},
+        program::Program,
+        struct_def::StructDef,
+        typing::TypePrimitive,
+    };
+
+    use super::{decode_return, generate, identifier, method_call, pascal_case, snake_case};
+
+    /// Output of [`generate`] for [`spec`], compiled as part of the tests
+    #[allow(dead_code)]
+    mod generated {
+        include!("../tests/fixtures/calculator.rs");
+    }
+
+    fn spec() -> AppSpec {
+        let contract = Contract {
+            doc: Some(" A calculator.".to_string()),
+            schema_global: StructDef {
+                fields: HashMap::from([("lastResult", TypePrimitive::UInt64)]),
+                ..Default::default()
+            },
+            schema_local: StructDef::default(),
+            schema_box: StructDef {
+                maps: HashMap::from([("history", (TypePrimitive::UInt64, TypePrimitive::UInt64))]),
+                ..Default::default()
+            },
+            events: vec![],
+            txn_approval: Program {
+                body: int!(1),
+                methods: vec![
+                    AbiMethod {
+                        name: "addTo".to_string(),
+                        args: vec![
+                            ("a".to_string(), AbiType::Uint(64)),
+                            ("who".to_string(), AbiType::Address),
+                        ],
+                        returns: Some(AbiType::Uint(64)),
+                        body: int!(1),
+                        doc: Some(" Adds to an account's total.".to_string()),
+                        create: false,
+                        on_complete: vec![OnComplete::NoOp, OnComplete::OptIn],
+                        creator_only: false,
+                        readonly: true,
+                    },
+                    AbiMethod {
+                        name: "type".to_string(),
+                        args: vec![
+                            ("match".to_string(), AbiType::Uint(64)),
+                            ("self".to_string(), AbiType::Bool),
+                        ],
+                        returns: Some(AbiType::Bool),
+                        body: int!(1),
+                        doc: None,
+                        create: false,
+                        on_complete: vec![],
+                        creator_only: false,
+                        readonly: false,
+                    },
+                ],
+                ..Default::default()
+            },
+            txn_clear: Program::default(),
+            templates: vec![],
+            lsigs: vec![],
+        };
+        AppSpec::new("calculator", &contract).unwrap()
+    }
+
+    #[test]
+    fn test() {
+        let generated = generate(&spec());
+        println!("{generated}");
+        assert!(generated.contains(
+            "/// A calculator.\n#[derive(Debug, Clone, Copy, PartialEq)]\npub struct Calculator {"
+        ));
+        assert!(generated.contains("pub const GLOBAL_NUM_UINTS: u64 = 1;"));
+        assert!(generated.contains(
+            "    /// Adds to an account's total.\n    ///\n    /// Calls `addTo(uint64,address)uint64`.\n    /// Read-only, so it may be simulated instead.\n    pub fn add_to(&self, on_complete: OnComplete, a: u64, who: [u8; 32]) -> Result<AppCall, AbiError> {"
+        ));
+        assert!(generated.contains("            on_complete,\n"));
+        assert!(generated.contains("(\"address\", AbiValue::from(who)),"));
+        assert!(generated.contains("pub fn add_to_return(log: &[u8]) -> Result<u64, AbiError> {"));
+        assert!(generated.contains(
+            "pub fn global_last_result(state: &State) -> Option<u64> {\n        state.get(\"lastResult\".as_bytes())?.as_uint()"
+        ));
+        assert!(generated.contains("pub fn history_box(key: u64) -> Vec<u8> {"));
+        assert!(generated.ends_with("    }\n}\n"));
+    }
+
+    #[test]
+    fn generated_module() {
+        // the programs embed label ids, which depend on the order tests run in
+        let code = |module: &str| {
+            module
+                .lines()
+                .filter(|line| !line.contains("_PROGRAM: &'static str"))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            code(&generate(&spec())),
+            code(include_str!("../tests/fixtures/calculator.rs")),
+            "tests/fixtures/calculator.rs is out of date"
+        );
+        let call = generated::Calculator::new(5)
+            .add_to(OnComplete::OptIn, 1, [0; 32])
+            .unwrap();
+        assert_eq!((call.app_id, call.args.len()), (5, 3));
+        let log = [RETURN_PREFIX.as_slice(), &[0, 0, 0, 0, 0, 0, 0, 7]].concat();
+        assert_eq!(generated::Calculator::add_to_return(&log).unwrap(), 7);
+        let call = generated::Calculator::new(5).r#type(2, true).unwrap();
+        assert_eq!(call.on_complete, OnComplete::NoOp);
+        assert_eq!(
+            generated::Calculator::history_box(1),
+            b"history\0\0\0\0\0\0\0\x01"
+        );
+    }
+
+    #[test]
+    fn runtime() {
+        let call = method_call(
+            5,
+            OnComplete::NoOp,
+            [1, 2, 3, 4],
+            vec![("uint16", AbiValue::from(258u64)), ("string", "a".into())],
+        )
+        .unwrap();
+        assert_eq!(call.args, [vec![1, 2, 3, 4], vec![1, 2], vec![0, 1, b'a']]);
+        assert!(method_call(5, OnComplete::NoOp, [0; 4], vec![("uint8", 256u64.into())]).is_err());
+
+        let log = [RETURN_PREFIX.as_slice(), &[0, 0, 0, 0, 0, 0, 0, 7]].concat();
+        assert_eq!(decode_return::<u64>("uint64", &log).unwrap(), 7);
+        assert!(decode_return::<u64>("uint64", &log[4..]).is_err());
+        assert!(decode_return::<bool>("uint64", &log).is_err());
+
+        assert_eq!(snake_case("addTo"), "add_to");
+        assert_eq!(identifier("type"), "r#type");
+        assert_eq!(identifier("self"), "self_");
+        assert_eq!(identifier("addTo"), "add_to");
+        assert_eq!(pascal_case("my_app"), "MyApp");
+    }
+}
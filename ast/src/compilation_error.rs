@@ -1,4 +1,8 @@
-use crate::{context::CompilationBinding, typing::TypeError};
+use crate::{
+    context::{CompilationBinding, Mode},
+    expression::{binary::Binary, constant::OnComplete},
+    typing::TypeError,
+};
 use std::string::FromUtf8Error;
 
 use thiserror::Error;
@@ -15,4 +19,40 @@ pub enum CompilationError {
     MissingStack,
     #[error("Attempt to assign to constant expression: {0:?}")]
     ConstantAssignment(CompilationBinding),
+    #[error("{0} schema declares {1} keys, exceeding the limit of {2}")]
+    TooManyStateKeys(String, u64, u64),
+    #[error("State key {0} is longer than 64 bytes")]
+    KeyTooLong(String),
+    #[error("State key {0} and its value of {1} bytes exceed 128 bytes")]
+    StateEntryTooLarge(String, usize),
+    #[error("Field {0} cannot be packed; packed fields must be uint64 or fixed-width bytes")]
+    UnpackableField(String),
+    #[error("Packed field {0} must be written with a value of {1} bytes")]
+    PackedWidthMismatch(String, usize),
+    #[error("Packed field {0} cannot be deleted")]
+    PackedFieldDeletion(String),
+    #[error("A value of {1} bytes cannot be encoded as {0}")]
+    AbiValueLength(String, usize),
+    #[error("Method {0} takes more than 15 arguments")]
+    TooManyMethodArgs(String),
+    #[error("Duplicate method selector for {0}")]
+    DuplicateMethod(String),
+    #[error("Method {0} cannot be called with OnCompletion {1:?}")]
+    InvalidOnComplete(String, OnComplete),
+    #[error("Group index of {0} must be a constant")]
+    NonConstantGroupIndex(String),
+    #[error("{0} requires TEAL version {1}, but the program targets version {2}")]
+    UnsupportedVersion(String, u64, u64),
+    #[error("{0} is only available in {1:?} mode, but the program runs in {2:?} mode")]
+    UnsupportedMode(String, Mode, Mode),
+    #[error("No value given for template variable {0}")]
+    MissingTemplateValue(String),
+    #[error("Value of template variable {0} has the wrong type")]
+    TemplateTypeMismatch(String),
+    #[error("Operand type of {0:?} was not resolved; type check the program before compiling")]
+    UnresolvedOperand(Binary),
+    #[error("Malformed bytecode: {0}")]
+    MalformedBytecode(&'static str),
+    #[error("Malformed source map: {0}")]
+    MalformedSourceMap(&'static str),
 }
@@ -1,17 +1,33 @@
 use std::rc::Rc;
 
-use crate::typing::TypeEnum;
+use crate::{
+    compilation_error::CompilationError,
+    event::Event,
+    struct_def::{PackedLayout, StructDef},
+    template::Template,
+    typing::TypeEnum,
+    MAX_TEAL_VERSION,
+};
 
 #[derive(Clone)]
 pub struct Scope<'a, K: PartialEq, V> {
-    item: Option<(K, V)>,
+    items: Vec<(K, V)>,
     parent: Option<&'a Scope<'a, K, V>>,
 }
 
 impl<'a, K: PartialEq, V> Default for Scope<'a, K, V> {
     fn default() -> Self {
         Self {
-            item: None,
+            items: vec![],
+            parent: None,
+        }
+    }
+}
+
+impl<'a, K: PartialEq, V> FromIterator<(K, V)> for Scope<'a, K, V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self {
+            items: iter.into_iter().collect(),
             parent: None,
         }
     }
@@ -19,21 +35,20 @@ impl<'a, K: PartialEq, V> Default for Scope<'a, K, V> {
 
 impl<'a, K: PartialEq, V> Scope<'a, K, V> {
     pub fn get(&self, search: &K) -> Option<&V> {
-        match self {
-            Scope {
-                item: Some((k, v)), ..
-            } if k == search => Some(v),
-            Scope {
-                parent: Some(parent),
-                ..
-            } => parent.get(search),
-            _ => None,
+        match self.items.iter().rev().find(|(k, _)| k == search) {
+            Some((_, v)) => Some(v),
+            None => self.parent.and_then(|parent| parent.get(search)),
         }
     }
 
     pub fn add(&'a self, k: K, v: V) -> Self {
+        self.add_all(vec![(k, v)])
+    }
+
+    /// Adds several bindings at once; later items shadow earlier ones.
+    pub fn add_all(&'a self, items: Vec<(K, V)>) -> Self {
         Self {
-            item: Some((k, v)),
+            items,
             parent: Some(self),
         }
     }
@@ -44,12 +59,90 @@ pub struct TypeContext<'a> {
     pub bind_scope: Rc<Scope<'a, String, TypeEnum>>,
     pub global_scope: Rc<Scope<'a, String, TypeEnum>>,
     pub local_scope: Rc<Scope<'a, String, TypeEnum>>,
+    pub box_scope: Rc<Scope<'a, String, TypeEnum>>,
+    pub template_scope: Rc<Scope<'a, String, TypeEnum>>,
+    pub events: Rc<Vec<Event>>,
+}
+
+impl<'a> TypeContext<'a> {
+    /// Context with `binds` added to the bind scope, sharing everything else.
+    pub fn with_binds<'b>(&'b self, binds: Vec<(String, TypeEnum)>) -> TypeContext<'b> {
+        TypeContext {
+            bind_scope: Rc::new(self.bind_scope.add_all(binds)),
+            global_scope: Rc::clone(&self.global_scope),
+            local_scope: Rc::clone(&self.local_scope),
+            box_scope: Rc::clone(&self.box_scope),
+            template_scope: Rc::clone(&self.template_scope),
+            events: Rc::clone(&self.events),
+        }
+    }
+}
+
+/// Programs run either as an application (approval/clear state) or as a logic
+/// signature, and some opcodes are only available in one of the two.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Mode {
+    #[default]
+    Application,
+    Signature,
 }
 
-#[derive(Default)]
 pub struct CompilationContext<'a> {
     pub scope: Scope<'a, String, CompilationBinding>,
     pub scratch_id: u8,
+    pub version: u64,
+    pub mode: Mode,
+    /// Layouts of the global and local schemas, if they are packed
+    pub global_layout: Option<&'a PackedLayout>,
+    pub local_layout: Option<&'a PackedLayout>,
+    /// Declared boxes, which are needed to encode their keys and values
+    pub box_schema: Option<&'a StructDef<'a>>,
+    /// Declared events, which can be emitted by name
+    pub events: &'a [Event],
+    /// Declared template variables, loaded from the program's constant blocks
+    pub templates: &'a [Template],
+}
+
+impl<'a> Default for CompilationContext<'a> {
+    fn default() -> Self {
+        Self {
+            scope: Scope::default(),
+            scratch_id: 0,
+            version: MAX_TEAL_VERSION,
+            mode: Mode::default(),
+            global_layout: None,
+            local_layout: None,
+            box_schema: None,
+            events: &[],
+            templates: &[],
+        }
+    }
+}
+
+impl<'a> CompilationContext<'a> {
+    pub fn require_version(&self, what: &str, required: u64) -> Result<(), CompilationError> {
+        if self.version < required {
+            Err(CompilationError::UnsupportedVersion(
+                what.to_string(),
+                required,
+                self.version,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn require_mode(&self, what: &str, required: Mode) -> Result<(), CompilationError> {
+        if self.mode != required {
+            Err(CompilationError::UnsupportedMode(
+                what.to_string(),
+                required,
+                self.mode,
+            ))
+        } else {
+            Ok(())
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1,8 +1,244 @@
-use crate::{program::Program, struct_def::StructDef};
+use std::{collections::BTreeMap, rc::Rc};
+
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, TypeContext},
+    event::Event,
+    lsig::LogicSig,
+    method::AbiMethod,
+    program::Program,
+    struct_def::{StateSchema, StructDef, MAX_GLOBAL_KEYS, MAX_LOCAL_KEYS},
+    template::Template,
+    typing::{TypeEnum, TypeError},
+};
 
 pub struct Contract<'a> {
+    /// Doc comment from source, carried into the contract description
+    pub doc: Option<String>,
     pub schema_global: StructDef<'a>,
     pub schema_local: StructDef<'a>,
+    pub schema_box: StructDef<'a>,
+    pub events: Vec<Event>,
+    pub templates: Vec<Template>,
     pub txn_approval: Program,
     pub txn_clear: Program,
+    pub lsigs: Vec<LogicSig>,
+}
+
+pub struct CompiledContract {
+    pub approval: String,
+    pub clear: String,
+    pub global_schema: StateSchema,
+    pub local_schema: StateSchema,
+    /// Declared events, for decoding the logs they produce off-chain
+    pub events: Vec<Event>,
+    /// Methods of the approval program, for describing the contract's interface
+    pub methods: Vec<AbiMethod>,
+    pub doc: Option<String>,
+    /// TEAL of each logic signature, by name
+    pub lsigs: BTreeMap<String, String>,
+    /// Template variables whose placeholders must be substituted before deploying
+    pub templates: Vec<Template>,
+}
+
+impl<'a> Contract<'a> {
+    /// Type checks all programs against the declared state schemas. Logic
+    /// signatures cannot access state, which is reported when compiling them.
+    pub fn type_check(&self) -> Result<(), TypeError> {
+        let context = TypeContext {
+            global_scope: Rc::new(self.schema_global.type_scope()),
+            local_scope: Rc::new(self.schema_local.type_scope()),
+            box_scope: Rc::new(self.schema_box.type_scope()),
+            template_scope: Rc::new(
+                self.templates
+                    .iter()
+                    .map(|t| (t.name.clone(), TypeEnum::Simple(t.primitive.clone())))
+                    .collect(),
+            ),
+            events: Rc::new(self.events.clone()),
+            ..Default::default()
+        };
+        self.txn_approval.type_check_in(&context)?;
+        self.txn_clear.type_check_in(&context)?;
+        self.lsigs
+            .iter()
+            .try_for_each(|lsig| lsig.type_check(&context))
+    }
+
+    pub fn compile(&self) -> Result<CompiledContract, CompilationError> {
+        let global_schema = self.schema_global.validate("global", MAX_GLOBAL_KEYS)?;
+        let local_schema = self.schema_local.validate("local", MAX_LOCAL_KEYS)?;
+        self.type_check()?;
+        let global_layout = self.schema_global.packed_layout()?;
+        let local_layout = self.schema_local.packed_layout()?;
+        let context = CompilationContext {
+            global_layout: global_layout.as_ref(),
+            local_layout: local_layout.as_ref(),
+            box_schema: Some(&self.schema_box),
+            events: &self.events,
+            templates: &self.templates,
+            ..Default::default()
+        };
+        Ok(CompiledContract {
+            approval: self.txn_approval.compile_in(&context)?,
+            clear: self.txn_clear.compile_in(&context)?,
+            global_schema,
+            local_schema,
+            events: self.events.clone(),
+            methods: self.txn_approval.methods.clone(),
+            doc: self.doc.clone(),
+            lsigs: self
+                .lsigs
+                .iter()
+                .map(|lsig| Ok((lsig.name.clone(), lsig.compile(&context)?)))
+                .collect::<Result<_, CompilationError>>()?,
+            templates: self.templates.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        apply, bytes,
+        compilation_error::CompilationError,
+        expression::{
+            apply::Apply,
+            primitive::Primitive,
+            seq::Seq,
+            var::{LVal, RVal, Var},
+            Expr,
+        },
+        int,
+        program::Program,
+        seq,
+        struct_def::{StateSchema, StructDef},
+        typing::TypePrimitive,
+    };
+
+    use super::Contract;
+
+    fn contract(approval: Expr) -> Contract<'static> {
+        Contract {
+            doc: None,
+            schema_global: StructDef {
+                fields: HashMap::from([("counter", TypePrimitive::UInt64)]),
+                ..Default::default()
+            },
+            schema_local: StructDef {
+                fields: HashMap::from([("name", TypePrimitive::Byteslice)]),
+                ..Default::default()
+            },
+            txn_approval: Program {
+                body: approval,
+                ..Default::default()
+            },
+            schema_box: StructDef::default(),
+            events: vec![],
+            txn_clear: Program::default(),
+            templates: vec![],
+            lsigs: vec![],
+        }
+    }
+
+    #[test]
+    fn declared_state() {
+        let compiled = contract(seq!(
+            apply!(@fn Expr::LVal(LVal(Var::Global("counter".to_string()))); @arg int!(1));
+            Expr::RVal(RVal(Var::Global("counter".to_string())));
+        ))
+        .compile()
+        .unwrap();
+        println!("{}", compiled.approval);
+        assert_eq!(compiled.clear, "#pragma version 5\nint 0");
+        assert_eq!(
+            compiled.global_schema,
+            StateSchema {
+                num_uints: 1,
+                num_byte_slices: 0
+            }
+        );
+        assert_eq!(compiled.local_schema.num_byte_slices, 1);
+    }
+
+    #[test]
+    fn schema_limits() {
+        let mut c = contract(int!(1));
+        let keys = (0..17).map(|i| format!("key{i}")).collect::<Vec<_>>();
+        c.schema_local.fields = keys
+            .iter()
+            .map(|k| (k.as_str(), TypePrimitive::UInt64))
+            .collect();
+        assert!(matches!(
+            c.compile(),
+            Err(CompilationError::TooManyStateKeys(_, 17, 16))
+        ));
+
+        let long_value = contract(apply!(
+            @fn Expr::LVal(LVal(Var::Local("name".to_string())));
+            @arg int!(0);
+            @arg bytes!(vec![0; 125]);
+        ));
+        assert!(matches!(
+            long_value.compile(),
+            Err(CompilationError::StateEntryTooLarge(_, 125))
+        ));
+    }
+
+    #[test]
+    fn undeclared_or_mistyped_state() {
+        let undeclared = contract(Expr::RVal(RVal(Var::Global("missing".to_string()))));
+        assert!(undeclared.type_check().is_err());
+
+        let mistyped = contract(seq!(
+            apply!(
+                @fn Expr::LVal(LVal(Var::Local("name".to_string())));
+                @arg int!(0);
+                @arg int!(1);
+            );
+            int!(1);
+        ));
+        assert!(mistyped.type_check().is_err());
+    }
+
+    #[test]
+    fn packed_schema() {
+        let mut c = contract(seq!(
+            apply!(@fn Expr::LVal(LVal(Var::Global("counter".to_string()))); @arg int!(1));
+            Expr::RVal(RVal(Var::Global("counter".to_string())));
+        ));
+        c.schema_global = StructDef {
+            fields: HashMap::from([
+                ("counter", TypePrimitive::UInt64),
+                ("owner", TypePrimitive::Byteslice),
+            ]),
+            order: vec!["counter", "owner"],
+            widths: HashMap::from([("owner", 32)]),
+            packed: true,
+            ..Default::default()
+        };
+        c.txn_approval.version = 7;
+        let compiled = c.compile().unwrap();
+        println!("{}", compiled.approval);
+        assert_eq!(
+            compiled.global_schema,
+            StateSchema {
+                num_uints: 0,
+                num_byte_slices: 1
+            }
+        );
+        assert!(compiled.approval.contains("int 40\nbzero"));
+        assert!(compiled
+            .approval
+            .contains("int 1\nitob\nreplace2 0\napp_global_put"));
+        assert!(compiled.approval.ends_with("extract 0 8\nbtoi"));
+
+        c.schema_global.widths.clear();
+        assert!(matches!(
+            c.compile(),
+            Err(CompilationError::UnpackableField(_))
+        ));
+    }
 }
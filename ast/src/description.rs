@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    abi::{selector, AbiType},
+    contract::CompiledContract,
+    event::Event,
+    method::AbiMethod,
+};
+
+/// ARC-4 contract description (`contract.json`), used by front-ends and SDK
+/// clients to call the contract's methods.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractDescription {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    /// Application ids keyed by network genesis hash, filled in once deployed
+    #[serde(default)]
+    pub networks: BTreeMap<String, NetworkDescription>,
+    pub methods: Vec<MethodDescription>,
+    /// ARC-28 events logged by the contract
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EventDescription>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkDescription {
+    #[serde(rename = "appID")]
+    pub app_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodDescription {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    pub args: Vec<ArgDescription>,
+    pub returns: ReturnsDescription,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArgDescription {
+    #[serde(rename = "type")]
+    pub abi_type: AbiType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReturnsDescription {
+    /// `None` for `void`
+    #[serde(rename = "type", with = "void_or_abi_type")]
+    pub abi_type: Option<AbiType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventDescription {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    pub args: Vec<ArgDescription>,
+}
+
+mod void_or_abi_type {
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::abi::AbiType;
+
+    pub fn serialize<S: Serializer>(t: &Option<AbiType>, serializer: S) -> Result<S::Ok, S::Error> {
+        match t {
+            Some(t) => serializer.collect_str(t),
+            None => serializer.serialize_str("void"),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<AbiType>, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "void" => Ok(None),
+            s => AbiType::from_str(s)
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// A doc comment split into its description and `@param name ...` and
+/// `@returns ...` tags.
+#[derive(Default)]
+struct Doc<'a> {
+    desc: Vec<&'a str>,
+    params: HashMap<&'a str, &'a str>,
+    returns: Option<&'a str>,
+}
+
+impl<'a> Doc<'a> {
+    fn parse(doc: Option<&'a str>) -> Self {
+        let mut parsed = Doc::default();
+        for line in doc.unwrap_or_default().lines().map(str::trim) {
+            if let Some(param) = line.strip_prefix("@param ") {
+                let (name, desc) = param.trim().split_once(' ').unwrap_or((param, ""));
+                parsed.params.insert(name, desc.trim());
+            } else if let Some(returns) = line.strip_prefix("@returns ") {
+                parsed.returns = Some(returns.trim());
+            } else {
+                parsed.desc.push(line);
+            }
+        }
+        parsed
+    }
+
+    fn desc(&self) -> Option<String> {
+        let desc = self.desc.join("\n").trim().to_string();
+        (!desc.is_empty()).then_some(desc)
+    }
+
+    fn args(&self, args: &[(String, AbiType)]) -> Vec<ArgDescription> {
+        args.iter()
+            .map(|(name, abi_type)| ArgDescription {
+                abi_type: abi_type.clone(),
+                name: Some(name.clone()),
+                desc: self.params.get(name.as_str()).map(|d| d.to_string()),
+            })
+            .collect()
+    }
+}
+
+impl MethodDescription {
+    fn new(method: &AbiMethod) -> Self {
+        let doc = Doc::parse(method.doc.as_deref());
+        MethodDescription {
+            name: method.name.clone(),
+            desc: doc.desc(),
+            args: doc.args(&method.args),
+            returns: ReturnsDescription {
+                abi_type: method.returns.clone(),
+                desc: doc.returns.map(str::to_string),
+            },
+        }
+    }
+
+    /// Signature used to derive the selector, e.g. `add(uint64,uint64)uint64`.
+    pub fn signature(&self) -> String {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.abi_type.to_string())
+            .collect::<Vec<_>>();
+        let returns = self
+            .returns
+            .abi_type
+            .as_ref()
+            .map_or("void".to_string(), |r| r.to_string());
+        format!("{}({}){}", self.name, args.join(","), returns)
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        selector(&self.signature())
+    }
+}
+
+impl EventDescription {
+    fn new(event: &Event) -> Self {
+        let doc = Doc::parse(event.doc.as_deref());
+        EventDescription {
+            name: event.name.clone(),
+            desc: doc.desc(),
+            args: doc.args(&event.args),
+        }
+    }
+}
+
+impl ContractDescription {
+    /// Describes the methods and events of a compiled contract. Networks are
+    /// left empty until the contract is deployed.
+    pub fn new(name: &str, contract: &CompiledContract) -> Self {
+        ContractDescription {
+            name: name.to_string(),
+            desc: Doc::parse(contract.doc.as_deref()).desc(),
+            networks: BTreeMap::new(),
+            methods: contract
+                .methods
+                .iter()
+                .map(MethodDescription::new)
+                .collect(),
+            events: contract.events.iter().map(EventDescription::new).collect(),
+        }
+    }
+
+    pub fn method(&self, name: &str) -> Option<&MethodDescription> {
+        self.methods.iter().find(|m| m.name == name)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, str::FromStr};
+
+    use crate::{
+        abi::AbiType,
+        contract::CompiledContract,
+        event::Event,
+        expression::{primitive::Primitive, Expr},
+        int,
+        method::AbiMethod,
+        struct_def::StateSchema,
+    };
+
+    use super::ContractDescription;
+
+    fn compiled() -> CompiledContract {
+        CompiledContract {
+            approval: String::new(),
+            clear: String::new(),
+            global_schema: StateSchema::default(),
+            local_schema: StateSchema::default(),
+            events: vec![Event {
+                name: "Added".to_string(),
+                args: vec![("sum".to_string(), AbiType::Uint(64))],
+                doc: None,
+            }],
+            methods: vec![
+                AbiMethod {
+                    name: "add".to_string(),
+                    args: vec![
+                        ("a".to_string(), AbiType::Uint(64)),
+                        ("b".to_string(), AbiType::Uint(64)),
+                    ],
+                    returns: Some(AbiType::Uint(64)),
+                    body: int!(0),
+                    doc: Some(
+                        " Adds two numbers.\n @param a first summand\n @returns the sum"
+                            .to_string(),
+                    ),
+                    create: false,
+                    on_complete: vec![],
+                    creator_only: false,
+                    readonly: false,
+                },
+                AbiMethod {
+                    name: "reset".to_string(),
+                    args: vec![],
+                    returns: None,
+                    body: int!(0),
+                    doc: None,
+                    create: false,
+                    on_complete: vec![],
+                    creator_only: false,
+                    readonly: false,
+                },
+            ],
+            doc: Some(" A calculator.".to_string()),
+            lsigs: BTreeMap::new(),
+            templates: vec![],
+        }
+    }
+
+    #[test]
+    fn test() {
+        let description = ContractDescription::new("Calculator", &compiled());
+        let json = description.to_json();
+        println!("{json}");
+        assert!(json.contains("\"desc\": \"A calculator.\""));
+        assert!(json.contains("\"networks\": {}"));
+        assert!(json.contains("\"type\": \"void\""));
+
+        let add = description.method("add").unwrap();
+        assert_eq!(add.desc.as_deref(), Some("Adds two numbers."));
+        assert_eq!(add.args[0].desc.as_deref(), Some("first summand"));
+        assert_eq!(add.args[1].desc, None);
+        assert_eq!(add.returns.desc.as_deref(), Some("the sum"));
+        assert_eq!(add.selector(), [0xfe, 0x6b, 0xdf, 0x69]);
+
+        assert_eq!(ContractDescription::from_json(&json).unwrap(), description);
+    }
+
+    #[test]
+    fn read_back() {
+        // as produced by other ARC-4 tooling
+        let json = r#"{
+            "name": "Calculator",
+            "networks": {
+                "wGHE2Pwdvd7S12BL5FaOP20EGYesN73ktiC1qzkkit8=": { "appID": 1234 }
+            },
+            "methods": [
+                {
+                    "name": "mul",
+                    "args": [{ "type": "uint64" }, { "type": "(uint64, bool)[]", "name": "b" }],
+                    "returns": { "type": "uint128", "desc": "The product" }
+                }
+            ]
+        }"#;
+        let description = ContractDescription::from_json(json).unwrap();
+        println!("{description:?}");
+        assert_eq!(description.networks.values().next().unwrap().app_id, 1234);
+        let mul = description.method("mul").unwrap();
+        assert_eq!(
+            mul.args[1].abi_type,
+            AbiType::from_str("(uint64,bool)[]").unwrap()
+        );
+        assert_eq!(mul.signature(), "mul(uint64,(uint64,bool)[])uint128");
+        assert!(description.events.is_empty());
+
+        assert!(ContractDescription::from_json(&json.replace("uint64", "uint65")).is_err());
+    }
+}
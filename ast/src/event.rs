@@ -0,0 +1,27 @@
+use crate::abi::{selector, AbiType};
+
+/// An ARC-28 event, e.g. `event Transfer(from: address, to: address, amount: uint64)`.
+/// Emitting it logs the event selector followed by the ABI-encoded arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub name: String,
+    pub args: Vec<(String, AbiType)>,
+    /// Doc comment from source, carried into the contract description
+    pub doc: Option<String>,
+}
+
+impl Event {
+    /// Signature used to derive the selector, e.g. `Transfer(address,address,uint64)`.
+    pub fn signature(&self) -> String {
+        let args = self
+            .args
+            .iter()
+            .map(|(_, abi_type)| abi_type.to_string())
+            .collect::<Vec<_>>();
+        format!("{}({})", self.name, args.join(","))
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        selector(&self.signature())
+    }
+}
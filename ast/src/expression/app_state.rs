@@ -0,0 +1,55 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{pop_args, Expression, Prepared};
+
+/// Reads of application state that report whether the key exists, including
+/// state of applications other than the current one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppStateEx {
+    /// `app_global_get_ex(application, key)`
+    Global,
+    /// `app_local_get_ex(account, application, key)`
+    Local,
+}
+
+impl AppStateEx {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "app_global_get_ex" => AppStateEx::Global,
+            "app_local_get_ex" => AppStateEx::Local,
+            _ => return None,
+        })
+    }
+}
+
+impl Expression for AppStateEx {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let value = TypeEnum::Option(Box::new(TypeEnum::Var(TypeVar::new())));
+        Ok(match self {
+            AppStateEx::Global => typesig!(int -> bytes -> #value),
+            // accounts may be given as an address or an index into Txn.Accounts
+            AppStateEx::Local => typesig!(:account -> int -> bytes -> #value),
+        })
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let (arity, opcode) = match self {
+            AppStateEx::Global => (2, "app_global_get_ex"),
+            AppStateEx::Local => (3, "app_local_get_ex"),
+        };
+        context.require_version(opcode, 2)?;
+        let mut pieces = pop_args(prepared_stack, arity)?;
+        pieces.push(opcode.to_string());
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
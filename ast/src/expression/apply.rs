@@ -4,7 +4,7 @@ use crate::{
     typing::{TypeEnum, TypeError},
 };
 
-use super::{Expr, Expression};
+use super::{Expr, Expression, Prepared};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Apply(pub Expr, pub Expr);
@@ -16,6 +16,9 @@ impl Expression for Apply {
         match f_type {
             TypeEnum::Arrow(ref mut param_type, body_type) => {
                 param_type.unify(&mut arg_type)?;
+                if let Expr::Operator(operator) = &self.0 {
+                    operator.check_operand()?;
+                }
                 Ok(*body_type)
             }
             _ => Err(TypeError::NonFunctionApplication(f_type)),
@@ -25,10 +28,14 @@ impl Expression for Apply {
     fn compile(
         &self,
         context: &CompilationContext,
-        prepared_stack: &mut Vec<String>,
+        prepared_stack: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
-        let arg = self.1.compile(context, prepared_stack)?;
-        prepared_stack.push(arg);
+        let constant = match &self.1 {
+            Expr::Primitive(primitive) => Some(primitive.clone()),
+            _ => None,
+        };
+        let compiled = self.1.compile(context, prepared_stack)?;
+        prepared_stack.push(Prepared { compiled, constant });
         let f = self.0.compile(context, prepared_stack)?;
         Ok(f)
     }
@@ -0,0 +1,67 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, Mode, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{pop_arg, Expression, Prepared};
+
+/// `arg(i)`, the `i`th argument of a logic signature. Constant indexes use
+/// `arg i`, others `args`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arg;
+
+impl Expression for Arg {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        Ok(typesig!(int -> bytes))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        context.require_mode("arg", Mode::Signature)?;
+        let index = pop_arg(prepared_stack)?;
+        match index.immediate() {
+            Some(i) => Ok(format!("arg {i}")),
+            None => {
+                context.require_version("args", 5)?;
+                Ok(format!("{}{OP_SEPARATOR}args", index.compiled))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply,
+        context::{CompilationContext, Mode},
+        expression::{apply::Apply, primitive::Primitive, txn::Txn, Expr, Expression},
+        int,
+    };
+
+    use super::Arg;
+
+    #[test]
+    fn test() {
+        let signature = CompilationContext {
+            mode: Mode::Signature,
+            ..Default::default()
+        };
+        let e = apply!(@fn Expr::Arg(Arg); @arg int!(1));
+        assert_eq!(e.compile(&signature, &mut vec![]).unwrap(), "arg 1");
+
+        let e = apply!(@fn Expr::Arg(Arg); @arg Expr::Txn(Txn::GroupIndex));
+        assert_eq!(
+            e.compile(&signature, &mut vec![]).unwrap(),
+            "txn GroupIndex\nargs"
+        );
+        assert!(e
+            .compile(&CompilationContext::default(), &mut vec![])
+            .is_err());
+    }
+}
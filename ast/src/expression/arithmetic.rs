@@ -0,0 +1,195 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{pop_args, Expression, Prepared};
+
+/// Built-in arithmetic functions beyond the overloaded operators, including
+/// conversions between `int` and `biguint`. Arguments are applied in call
+/// order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arithmetic {
+    /// `sqrt(a)`: integer square root
+    Sqrt,
+    /// `bsqrt(a)`: integer square root of a `biguint`
+    BigSqrt,
+    /// `divw(high, low, divisor)`: divides a 128-bit integer, failing if the
+    /// quotient does not fit in 64 bits
+    Divw,
+    /// `mulw(a, b)`: the 128-bit product as `(high, low)`
+    Mulw,
+    /// `addw(a, b)`: the 128-bit sum as `(carry, low)`
+    Addw,
+    /// `expw(a, b)`: the 128-bit power as `(high, low)`
+    Expw,
+    /// `divmodw(a_high, a_low, b_high, b_low)`: the 128-bit quotient and
+    /// remainder as `(quotient_high, quotient_low, remainder_high,
+    /// remainder_low)`
+    Divmodw,
+    /// `muldiv(a, b, c)`: `a * b / c` without intermediate overflow
+    MulDiv,
+    /// `biguint(a)`
+    ToBigUInt,
+    /// `uint64(a)`, which fails if the value does not fit in 64 bits
+    FromBigUInt,
+}
+
+impl Arithmetic {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sqrt" => Arithmetic::Sqrt,
+            "bsqrt" => Arithmetic::BigSqrt,
+            "divw" => Arithmetic::Divw,
+            "mulw" => Arithmetic::Mulw,
+            "addw" => Arithmetic::Addw,
+            "expw" => Arithmetic::Expw,
+            "divmodw" => Arithmetic::Divmodw,
+            "muldiv" => Arithmetic::MulDiv,
+            "biguint" => Arithmetic::ToBigUInt,
+            "uint64" => Arithmetic::FromBigUInt,
+            _ => return None,
+        })
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Arithmetic::Divmodw => 4,
+            Arithmetic::Divw | Arithmetic::MulDiv => 3,
+            Arithmetic::Mulw | Arithmetic::Addw | Arithmetic::Expw => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl Expression for Arithmetic {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let wide = TypeEnum::Tuple(vec![typesig!(int), typesig!(int)]);
+        Ok(match self {
+            Arithmetic::Mulw | Arithmetic::Addw | Arithmetic::Expw => {
+                typesig!(int -> int -> #wide)
+            }
+            Arithmetic::Divmodw => {
+                let quotient_and_remainder = TypeEnum::Tuple(vec![typesig!(int); 4]);
+                typesig!(int -> int -> int -> int -> #quotient_and_remainder)
+            }
+            Arithmetic::Sqrt => typesig!(int -> int),
+            Arithmetic::BigSqrt => typesig!(biguint -> biguint),
+            Arithmetic::Divw | Arithmetic::MulDiv => typesig!(int -> int -> int -> int),
+            Arithmetic::ToBigUInt => typesig!(int -> biguint),
+            Arithmetic::FromBigUInt => typesig!(biguint -> int),
+        })
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let args = pop_args(prepared_stack, self.arity())?;
+        let pieces = match (self, &args[..]) {
+            (Arithmetic::Sqrt, [a]) => {
+                context.require_version("sqrt", 4)?;
+                vec![a.clone(), "sqrt".to_string()]
+            }
+            (Arithmetic::BigSqrt, [a]) => {
+                context.require_version("bsqrt", 6)?;
+                vec![a.clone(), "bsqrt".to_string()]
+            }
+            (Arithmetic::Divw, [high, low, divisor]) => {
+                context.require_version("divw", 6)?;
+                vec![
+                    high.clone(),
+                    low.clone(),
+                    divisor.clone(),
+                    "divw".to_string(),
+                ]
+            }
+            (Arithmetic::MulDiv, [a, b, c]) => {
+                context.require_version("muldiv", 6)?;
+                // mulw leaves the high and low words of the product for divw
+                vec![
+                    a.clone(),
+                    b.clone(),
+                    "mulw".to_string(),
+                    c.clone(),
+                    "divw".to_string(),
+                ]
+            }
+            (Arithmetic::Mulw, [a, b]) => vec![a.clone(), b.clone(), "mulw".to_string()],
+            (Arithmetic::Addw, [a, b]) => {
+                context.require_version("addw", 2)?;
+                vec![a.clone(), b.clone(), "addw".to_string()]
+            }
+            (Arithmetic::Expw, [a, b]) => {
+                context.require_version("expw", 4)?;
+                vec![a.clone(), b.clone(), "expw".to_string()]
+            }
+            (Arithmetic::Divmodw, [a_high, a_low, b_high, b_low]) => {
+                context.require_version("divmodw", 4)?;
+                vec![
+                    a_high.clone(),
+                    a_low.clone(),
+                    b_high.clone(),
+                    b_low.clone(),
+                    "divmodw".to_string(),
+                ]
+            }
+            (Arithmetic::ToBigUInt, [a]) => vec![a.clone(), "itob".to_string()],
+            (Arithmetic::FromBigUInt, [a]) => vec![a.clone(), "btoi".to_string()],
+            _ => unreachable!(),
+        };
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply,
+        context::{CompilationContext, TypeContext},
+        expression::{
+            apply::Apply, arithmetic::Arithmetic, primitive::Primitive, Expr, Expression,
+        },
+        int,
+    };
+
+    #[test]
+    fn muldiv() {
+        let e = apply!(
+            @fn Expr::Arithmetic(Arithmetic::MulDiv);
+            @arg int!(u64::MAX);
+            @arg int!(3);
+            @arg int!(4);
+        );
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            format!("int {}\nint 3\nmulw\nint 4\ndivw", u64::MAX)
+        );
+        let v5 = CompilationContext {
+            version: 5,
+            ..Default::default()
+        };
+        assert!(e.compile(&v5, &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn conversion() {
+        let e = apply!(
+            @fn Expr::Arithmetic(Arithmetic::FromBigUInt);
+            @arg apply!(@fn Expr::Arithmetic(Arithmetic::BigSqrt); @arg apply!(
+                @fn Expr::Arithmetic(Arithmetic::ToBigUInt);
+                @arg int!(16);
+            ));
+        );
+        e.resolve(&TypeContext::default()).unwrap();
+        assert_eq!(e.compile_raw().unwrap(), "int 16\nitob\nbsqrt\nbtoi");
+
+        let e = apply!(@fn Expr::Arithmetic(Arithmetic::FromBigUInt); @arg int!(16));
+        assert!(e.resolve(&TypeContext::default()).is_err());
+    }
+}
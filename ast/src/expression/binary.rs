@@ -6,7 +6,7 @@ use crate::{
     OP_SEPARATOR,
 };
 
-use super::Expression;
+use super::{pop_arg, Expression, Prepared};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Binary {
@@ -16,13 +16,37 @@ pub enum Binary {
     GreaterThanEquals,
     LessThan,
     LessThanEquals,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    And,
+    Or,
 }
 
-fn op(l: String, s: &str, r: String) -> Result<String, CompilationError> {
-    Ok(format!(
-        "{l}{OP_SEPARATOR}{r}{OP_SEPARATOR}{}",
-        s.to_string()
-    ))
+pub(super) fn op(l: String, s: &str, r: String) -> Result<String, CompilationError> {
+    Ok(format!("{l}{OP_SEPARATOR}{r}{OP_SEPARATOR}{s}"))
+}
+
+impl Binary {
+    /// Byte math counterpart of the operator, for `biguint` operands.
+    pub fn byte_math_opcode(&self) -> Option<&'static str> {
+        Some(match self {
+            Binary::Equals => "b==",
+            Binary::NotEquals => "b!=",
+            Binary::GreaterThan => "b>",
+            Binary::GreaterThanEquals => "b>=",
+            Binary::LessThan => "b<",
+            Binary::LessThanEquals => "b<=",
+            Binary::Add => "b+",
+            Binary::Subtract => "b-",
+            Binary::Multiply => "b*",
+            Binary::Divide => "b/",
+            Binary::Modulo => "b%",
+            Binary::And | Binary::Or => return None,
+        })
+    }
 }
 
 impl Expression for Binary {
@@ -33,7 +57,14 @@ impl Expression for Binary {
             Binary::GreaterThan
             | Binary::GreaterThanEquals
             | Binary::LessThan
-            | Binary::LessThanEquals => typesig!(int -> int -> int),
+            | Binary::LessThanEquals
+            | Binary::Add
+            | Binary::Subtract
+            | Binary::Multiply
+            | Binary::Divide
+            | Binary::Modulo
+            | Binary::And
+            | Binary::Or => typesig!(int -> int -> int),
             // function binary<T>(a: T, b: T): uint;
             // 'a -> 'a -> uint
             Binary::Equals | Binary::NotEquals => typesig!(:a -> :a -> int),
@@ -43,10 +74,10 @@ impl Expression for Binary {
     fn compile(
         &self,
         _: &CompilationContext,
-        prepared_stack: &mut Vec<String>,
+        prepared_stack: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
-        let b = prepared_stack.pop().ok_or(CompilationError::MissingStack)?;
-        let a = prepared_stack.pop().ok_or(CompilationError::MissingStack)?;
+        let b = pop_arg(prepared_stack)?.compiled;
+        let a = pop_arg(prepared_stack)?.compiled;
         match self {
             Binary::Equals => op(a, "==", b),
             Binary::NotEquals => op(a, "!=", b),
@@ -54,6 +85,13 @@ impl Expression for Binary {
             Binary::GreaterThanEquals => op(a, ">=", b),
             Binary::LessThan => op(a, "<", b),
             Binary::LessThanEquals => op(a, "<=", b),
+            Binary::Add => op(a, "+", b),
+            Binary::Subtract => op(a, "-", b),
+            Binary::Multiply => op(a, "*", b),
+            Binary::Divide => op(a, "/", b),
+            Binary::Modulo => op(a, "%", b),
+            Binary::And => op(a, "&&", b),
+            Binary::Or => op(a, "||", b),
         }
     }
 }
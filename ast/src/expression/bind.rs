@@ -1,13 +1,11 @@
-use std::rc::Rc;
-
 use crate::{
     compilation_error::CompilationError,
     context::{CompilationBinding, CompilationContext, TypeContext},
-    typing::{TypeEnum, TypeError},
+    typing::{TypeEnum, TypeError, TypeVar},
     OP_SEPARATOR,
 };
 
-use super::{primitive::Primitive, Expr, Expression};
+use super::{primitive::Primitive, Expr, Expression, Prepared};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Bind {
@@ -21,6 +19,13 @@ pub enum Bind {
         value: Primitive,
         body: Expr,
     },
+    /// Destructures an expression producing multiple values, e.g.
+    /// `let (hi, lo) = mulw(a, b);`
+    LetTuple {
+        identifiers: Vec<String>,
+        value: Expr,
+        body: Expr,
+    },
 }
 
 impl Expression for Bind {
@@ -28,8 +33,34 @@ impl Expression for Bind {
         let value_type = match self {
             Bind::Let { value, .. } => value.resolve(context),
             Bind::Const { value, .. } => value.resolve(context),
+            Bind::LetTuple {
+                identifiers,
+                value,
+                body,
+            } => {
+                let mut elements = identifiers
+                    .iter()
+                    .map(|_| TypeEnum::Var(TypeVar::new()))
+                    .collect::<Vec<_>>();
+                value
+                    .resolve(context)?
+                    .unify(&mut TypeEnum::Tuple(elements.clone()))?;
+                let bindings = identifiers
+                    .iter()
+                    .cloned()
+                    .zip(elements.iter_mut().map(|e| e.resolved()))
+                    .collect();
+                let context = context.with_binds(bindings);
+                return body.resolve(&context);
+            }
         }?;
 
+        match value_type.resolved() {
+            TypeEnum::Tuple(..) => return Err(TypeError::UndestructuredTuple(value_type)),
+            TypeEnum::Option(..) => return Err(TypeError::UnhandledOption(value_type)),
+            _ => {}
+        }
+
         match self {
             Bind::Let {
                 identifier, body, ..
@@ -37,20 +68,17 @@ impl Expression for Bind {
             | Bind::Const {
                 identifier, body, ..
             } => {
-                let context = TypeContext {
-                    bind_scope: Rc::new(context.bind_scope.add(identifier.to_string(), value_type)),
-                    global_scope: Rc::clone(&context.global_scope),
-                    local_scope: Rc::clone(&context.local_scope),
-                };
+                let context = context.with_binds(vec![(identifier.to_string(), value_type)]);
                 body.resolve(&context)
             }
+            Bind::LetTuple { .. } => unreachable!(),
         }
     }
 
     fn compile(
         &self,
         context: &CompilationContext,
-        _: &mut Vec<String>,
+        _: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
         match self {
             Bind::Const {
@@ -86,13 +114,49 @@ impl Expression for Bind {
                         CompilationBinding::ScratchVar(scratch_id),
                     ),
                     scratch_id: next_scratch_id,
+                    ..*context
                 };
                 let body_compiled = body.compile(&context, &mut vec![])?;
                 Ok(
-                    vec![value_compiled, format!("store {scratch_id}"), body_compiled]
+                    [value_compiled, format!("store {scratch_id}"), body_compiled]
                         .join(OP_SEPARATOR),
                 )
             }
+            Bind::LetTuple {
+                identifiers,
+                value,
+                body,
+            } => {
+                let value_compiled = value.compile(context, &mut Vec::new())?;
+                let first_scratch_id = context.scratch_id as usize;
+                let next_scratch_id = u8::try_from(first_scratch_id + identifiers.len())
+                    .map_err(|_| CompilationError::OutOfScratchSpace)?;
+                let bindings = identifiers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, identifier)| {
+                        (
+                            identifier.to_string(),
+                            CompilationBinding::ScratchVar((first_scratch_id + i) as u8),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                // the last value is on top of the stack, so it is stored first
+                let stores = (first_scratch_id..next_scratch_id as usize)
+                    .rev()
+                    .map(|id| format!("store {id}"));
+                let context = CompilationContext {
+                    scope: context.scope.add_all(bindings),
+                    scratch_id: next_scratch_id,
+                    ..*context
+                };
+                let body_compiled = body.compile(&context, &mut vec![])?;
+                Ok(std::iter::once(value_compiled)
+                    .chain(stores)
+                    .chain(std::iter::once(body_compiled))
+                    .collect::<Vec<_>>()
+                    .join(OP_SEPARATOR))
+            }
         }
     }
 }
@@ -101,14 +165,17 @@ impl Expression for Bind {
 mod tests {
     use super::*;
     use crate::{
+        apply,
         context::TypeContext,
         expression::{
             apply::Apply,
+            arithmetic::Arithmetic,
             binary::Binary,
             primitive::Primitive,
             var::{RVal, Var},
             Expression,
         },
+        int,
     };
 
     use super::Bind;
@@ -129,4 +196,50 @@ mod tests {
         println!("{:?}", e.resolve(&TypeContext::default()));
         println!("{}", e.compile_raw().unwrap());
     }
+
+    #[test]
+    fn destructure() {
+        let e = Bind::LetTuple {
+            identifiers: vec!["hi".to_string(), "lo".to_string()],
+            value: apply!(
+                @fn Expr::Arithmetic(Arithmetic::Mulw);
+                @arg int!(u64::MAX);
+                @arg int!(2);
+            ),
+            body: Expr::RVal(RVal(Var::Bind("lo".to_string()))),
+        };
+        e.resolve(&TypeContext::default()).unwrap();
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            format!("int {}\nint 2\nmulw\nstore 1\nstore 0\nload 1", u64::MAX)
+        );
+
+        let e = Bind::LetTuple {
+            identifiers: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            value: apply!(
+                @fn Expr::Arithmetic(Arithmetic::Mulw);
+                @arg int!(1);
+                @arg int!(2);
+            ),
+            body: int!(1),
+        };
+        assert!(e.resolve(&TypeContext::default()).is_err());
+    }
+
+    #[test]
+    fn undestructured() {
+        let e = Bind::Let {
+            identifier: "x".to_string(),
+            value: apply!(
+                @fn Expr::Arithmetic(Arithmetic::Mulw);
+                @arg int!(1);
+                @arg int!(2);
+            ),
+            body: int!(1),
+        };
+        assert!(matches!(
+            e.resolve(&TypeContext::default()),
+            Err(TypeError::UndestructuredTuple(_))
+        ));
+    }
 }
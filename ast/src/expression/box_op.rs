@@ -0,0 +1,152 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, Mode, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{pop_args, Expression, Prepared};
+
+/// Built-in box storage functions, taking the box name as their first
+/// argument. Boxes declared in `schema box` are usually accessed through
+/// `box.name` instead (see [`super::var::Var::Box`]).
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoxOp {
+    /// `box_create(name, size)`, returning 0 if the box already existed
+    Create,
+    /// `box_put(name, value)`
+    Put,
+    /// `box_get(name)`
+    Get,
+    /// `box_extract(name, offset, length)`
+    Extract,
+    /// `box_replace(name, offset, value)`
+    Replace,
+    /// `box_del(name)`, returning 0 if the box did not exist
+    Del,
+    /// `box_len(name)`
+    Len,
+    /// `box_resize(name, size)`
+    Resize,
+    /// `box_splice(name, offset, length, value)`
+    Splice,
+}
+
+impl BoxOp {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "box_create" => BoxOp::Create,
+            "box_put" => BoxOp::Put,
+            "box_get" => BoxOp::Get,
+            "box_extract" => BoxOp::Extract,
+            "box_replace" => BoxOp::Replace,
+            "box_del" => BoxOp::Del,
+            "box_len" => BoxOp::Len,
+            "box_resize" => BoxOp::Resize,
+            "box_splice" => BoxOp::Splice,
+            _ => return None,
+        })
+    }
+
+    fn opcode(&self) -> &'static str {
+        match self {
+            BoxOp::Create => "box_create",
+            BoxOp::Put => "box_put",
+            BoxOp::Get => "box_get",
+            BoxOp::Extract => "box_extract",
+            BoxOp::Replace => "box_replace",
+            BoxOp::Del => "box_del",
+            BoxOp::Len => "box_len",
+            BoxOp::Resize => "box_resize",
+            BoxOp::Splice => "box_splice",
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            BoxOp::Get | BoxOp::Del | BoxOp::Len => 1,
+            BoxOp::Create | BoxOp::Put | BoxOp::Resize => 2,
+            BoxOp::Extract | BoxOp::Replace => 3,
+            BoxOp::Splice => 4,
+        }
+    }
+}
+
+impl Expression for BoxOp {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let contents = TypeEnum::Option(Box::new(typesig!(bytes)));
+        let length = TypeEnum::Option(Box::new(typesig!(int)));
+        Ok(match self {
+            BoxOp::Create => typesig!(bytes -> int -> int),
+            BoxOp::Put => typesig!(bytes -> bytes -> void),
+            BoxOp::Get => typesig!(bytes -> #contents),
+            BoxOp::Extract => typesig!(bytes -> int -> int -> bytes),
+            BoxOp::Replace => typesig!(bytes -> int -> bytes -> void),
+            BoxOp::Del => typesig!(bytes -> int),
+            BoxOp::Len => typesig!(bytes -> #length),
+            BoxOp::Resize => typesig!(bytes -> int -> void),
+            BoxOp::Splice => typesig!(bytes -> int -> int -> bytes -> void),
+        })
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let opcode = self.opcode();
+        context.require_mode(opcode, Mode::Application)?;
+        context.require_version(
+            opcode,
+            match self {
+                BoxOp::Resize | BoxOp::Splice => 10,
+                _ => 8,
+            },
+        )?;
+        let mut pieces = pop_args(prepared_stack, self.arity())?;
+        pieces.push(opcode.to_string());
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply, bytes,
+        context::{CompilationContext, TypeContext},
+        expression::{apply::Apply, box_op::BoxOp, primitive::Primitive, Expr, Expression},
+        int,
+    };
+
+    #[test]
+    fn test() {
+        let e = apply!(
+            @fn Expr::BoxOp(BoxOp::Extract);
+            @arg bytes!("listing".into());
+            @arg int!(0);
+            @arg int!(32);
+        );
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "byte \"listing\"\nint 0\nint 32\nbox_extract"
+        );
+
+        let e = apply!(
+            @fn Expr::BoxOp(BoxOp::Resize);
+            @arg bytes!("listing".into());
+            @arg int!(64);
+        );
+        let v9 = CompilationContext {
+            version: 9,
+            ..Default::default()
+        };
+        assert!(e.compile(&v9, &mut vec![]).is_err());
+        let v10 = CompilationContext {
+            version: 10,
+            ..Default::default()
+        };
+        assert!(e.compile(&v10, &mut vec![]).is_ok());
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{pop_prepared, Expression, Prepared};
+
+/// Built-in byte string functions. Arguments are applied in call order, e.g.
+/// `substring(s, start, end)` is typed `bytes -> int -> int -> bytes`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ByteOp {
+    Concat,
+    Len,
+    Substring,
+    Extract,
+    Replace,
+    GetByte,
+    SetByte,
+    GetBit,
+    SetBit,
+    Itob,
+    Btoi,
+}
+
+impl ByteOp {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "concat" => ByteOp::Concat,
+            "len" => ByteOp::Len,
+            "substring" => ByteOp::Substring,
+            "extract" => ByteOp::Extract,
+            "replace" => ByteOp::Replace,
+            "getbyte" => ByteOp::GetByte,
+            "setbyte" => ByteOp::SetByte,
+            "getbit" => ByteOp::GetBit,
+            "setbit" => ByteOp::SetBit,
+            "itob" => ByteOp::Itob,
+            "btoi" => ByteOp::Btoi,
+            _ => return None,
+        })
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            ByteOp::Len | ByteOp::Itob | ByteOp::Btoi => 1,
+            ByteOp::Concat | ByteOp::GetByte | ByteOp::GetBit => 2,
+            ByteOp::Substring
+            | ByteOp::Extract
+            | ByteOp::Replace
+            | ByteOp::SetByte
+            | ByteOp::SetBit => 3,
+        }
+    }
+}
+
+impl Expression for ByteOp {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        Ok(match self {
+            ByteOp::Concat => typesig!(bytes -> bytes -> bytes),
+            ByteOp::Len | ByteOp::Btoi => typesig!(bytes -> int),
+            ByteOp::Itob => typesig!(int -> bytes),
+            ByteOp::Substring | ByteOp::Extract => typesig!(bytes -> int -> int -> bytes),
+            ByteOp::Replace => typesig!(bytes -> int -> bytes -> bytes),
+            ByteOp::GetByte => typesig!(bytes -> int -> int),
+            ByteOp::SetByte => typesig!(bytes -> int -> int -> bytes),
+            // getbit and setbit operate on both uint64 and byte strings
+            ByteOp::GetBit => typesig!(:a -> int -> int),
+            ByteOp::SetBit => typesig!(:a -> int -> int -> :a),
+        })
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let prepared = pop_prepared(prepared_stack, self.arity())?;
+        // literal arguments that fit the immediates of `substring`, `extract`, ...
+        let immediate = |i: usize| prepared[i].immediate();
+        let args = prepared
+            .iter()
+            .map(|arg| arg.compiled.clone())
+            .collect::<Vec<_>>();
+        let pieces = match (self, &args[..]) {
+            (ByteOp::Concat, [a, b]) => {
+                context.require_version("concat", 2)?;
+                vec![a.clone(), b.clone(), "concat".to_string()]
+            }
+            (ByteOp::Len, [a]) => vec![a.clone(), "len".to_string()],
+            (ByteOp::Itob, [a]) => vec![a.clone(), "itob".to_string()],
+            (ByteOp::Btoi, [a]) => vec![a.clone(), "btoi".to_string()],
+            (ByteOp::Substring, [s, start, end]) => {
+                context.require_version("substring", 2)?;
+                match (immediate(1), immediate(2)) {
+                    (Some(start), Some(end)) => vec![s.clone(), format!("substring {start} {end}")],
+                    _ => vec![
+                        s.clone(),
+                        start.clone(),
+                        end.clone(),
+                        "substring3".to_string(),
+                    ],
+                }
+            }
+            (ByteOp::Extract, [s, start, length]) => {
+                context.require_version("extract", 5)?;
+                // `extract S 0` extracts to the end of the string, unlike `extract3`
+                match (immediate(1), immediate(2).filter(|l| *l > 0)) {
+                    (Some(start), Some(length)) => {
+                        vec![s.clone(), format!("extract {start} {length}")]
+                    }
+                    _ => vec![
+                        s.clone(),
+                        start.clone(),
+                        length.clone(),
+                        "extract3".to_string(),
+                    ],
+                }
+            }
+            (ByteOp::Replace, [s, start, replacement]) => {
+                context.require_version("replace", 7)?;
+                match immediate(1) {
+                    Some(start) => {
+                        vec![s.clone(), replacement.clone(), format!("replace2 {start}")]
+                    }
+                    None => vec![
+                        s.clone(),
+                        start.clone(),
+                        replacement.clone(),
+                        "replace3".to_string(),
+                    ],
+                }
+            }
+            (ByteOp::GetByte | ByteOp::GetBit, [a, i]) => {
+                context.require_version("getbyte/getbit", 3)?;
+                let op = if *self == ByteOp::GetByte {
+                    "getbyte"
+                } else {
+                    "getbit"
+                };
+                vec![a.clone(), i.clone(), op.to_string()]
+            }
+            (ByteOp::SetByte | ByteOp::SetBit, [a, i, v]) => {
+                context.require_version("setbyte/setbit", 3)?;
+                let op = if *self == ByteOp::SetByte {
+                    "setbyte"
+                } else {
+                    "setbit"
+                };
+                vec![a.clone(), i.clone(), v.clone(), op.to_string()]
+            }
+            _ => unreachable!(),
+        };
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply, bytes,
+        context::TypeContext,
+        expression::{
+            apply::Apply, byte_op::ByteOp, primitive::Primitive, txn::Txn, Expr, Expression,
+        },
+        int,
+    };
+
+    #[test]
+    fn concat_len() {
+        let e = apply!(
+            @fn Expr::ByteOp(ByteOp::Len);
+            @arg apply!(
+                @fn Expr::ByteOp(ByteOp::Concat);
+                @arg bytes!("a".into());
+                @arg bytes!("b".into());
+            );
+        );
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "byte \"a\"\nbyte \"b\"\nconcat\nlen"
+        );
+    }
+
+    #[test]
+    fn immediate_forms() {
+        let e = apply!(
+            @fn Expr::ByteOp(ByteOp::Extract);
+            @arg Expr::Txn(Txn::Note);
+            @arg int!(2);
+            @arg int!(4);
+        );
+        e.resolve(&TypeContext::default()).unwrap();
+        assert_eq!(e.compile_raw().unwrap(), "txn Note\nextract 2 4");
+
+        let e = apply!(
+            @fn Expr::ByteOp(ByteOp::Substring);
+            @arg Expr::Txn(Txn::Note);
+            @arg int!(2);
+            @arg Expr::Txn(Txn::Fee);
+        );
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "txn Note\nint 2\ntxn Fee\nsubstring3"
+        );
+    }
+
+    #[test]
+    fn mistyped() {
+        let e = apply!(@fn Expr::ByteOp(ByteOp::Len); @arg int!(2));
+        assert!(e.resolve(&TypeContext::default()).is_err());
+    }
+}
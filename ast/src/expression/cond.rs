@@ -3,10 +3,10 @@ use crate::{
     context::{CompilationContext, TypeContext},
     label::create_label_id,
     typing::{TypeEnum, TypeError, TypePrimitive},
-    OP_SEPARATOR,
+    with_error_message, OP_SEPARATOR,
 };
 
-use super::{Expr, Expression};
+use super::{Expr, Expression, Prepared};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Cond(pub Expr, pub Expr, pub Option<Box<Cond>>);
@@ -29,23 +29,30 @@ impl Expression for Cond {
     fn compile(
         &self,
         context: &CompilationContext,
-        _: &mut Vec<String>,
+        _: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
         let label_id = format!("cond{}", create_label_id());
+        let end_label_id = format!("endcond{}", create_label_id());
         let Cond(test, body, continuation) = self;
 
+        // the remaining arms skip over the body of this one
         let continuation = if let Some(c) = continuation {
-            c.compile(context, &mut vec![])?
+            [
+                c.compile(context, &mut vec![])?,
+                format!("b {end_label_id}"),
+            ]
+            .join(OP_SEPARATOR)
         } else {
-            "err".to_string()
+            with_error_message("err", "no condition matched")
         };
 
-        let pieces = vec![
+        let pieces = [
             test.compile(context, &mut vec![])?,
             format!("bnz {label_id}"),
             continuation,
             format!("{label_id}:"),
             body.compile(context, &mut vec![])?,
+            format!("{end_label_id}:"),
         ];
 
         Ok(pieces.join(OP_SEPARATOR))
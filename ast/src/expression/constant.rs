@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
     compilation_error::CompilationError,
     context::{CompilationContext, TypeContext},
@@ -5,9 +7,9 @@ use crate::{
     typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
 };
 
-use super::Expression;
+use super::{Expression, Prepared};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum OnComplete {
     NoOp,
     OptIn,
@@ -25,7 +27,7 @@ impl Expression for OnComplete {
     fn compile(
         &self,
         _: &CompilationContext,
-        _: &mut Vec<String>,
+        _: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
         Ok(format!("int {self:?}"))
     }
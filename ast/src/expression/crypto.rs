@@ -0,0 +1,212 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, Mode, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{pop_args, Expression, Prepared};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    Secp256k1,
+    Secp256r1,
+}
+
+/// Built-in hash and signature verification functions. Arguments are applied
+/// in call order, matching the operand order of the underlying opcodes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Crypto {
+    Sha256,
+    Keccak256,
+    Sha512_256,
+    Sha3_256,
+    /// `ed25519verify(data, signature, public_key)`, which verifies the
+    /// signature over `"ProgData" || program_hash || data`.
+    Ed25519Verify,
+    /// `ed25519verify_bare(data, signature, public_key)`
+    Ed25519VerifyBare,
+    /// `ecdsa_verify(data, r, s, x, y)`
+    EcdsaVerify(Curve),
+    /// `ecdsa_pk_decompress(compressed_key)`, which pushes `(x, y)`, e.g.
+    /// `let (x, y) = ecdsa_pk_decompress(key);`
+    EcdsaPkDecompress(Curve),
+    /// `ecdsa_pk_recover(data, recovery_id, r, s)`, which pushes `(x, y)`
+    EcdsaPkRecover(Curve),
+    /// `vrf_verify(message, proof, public_key)`, which pushes
+    /// `(output, verified)`; `verified` must be checked.
+    VrfVerify,
+}
+
+impl Crypto {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (name, curve) = match name.strip_suffix("_secp256r1") {
+            Some(name) if name.starts_with("ecdsa_") => (name, Curve::Secp256r1),
+            _ => (name, Curve::Secp256k1),
+        };
+        Some(match name {
+            "sha256" => Crypto::Sha256,
+            "keccak256" => Crypto::Keccak256,
+            "sha512_256" => Crypto::Sha512_256,
+            "sha3_256" => Crypto::Sha3_256,
+            "ed25519verify" => Crypto::Ed25519Verify,
+            "ed25519verify_bare" => Crypto::Ed25519VerifyBare,
+            "ecdsa_verify" => Crypto::EcdsaVerify(curve),
+            "ecdsa_pk_decompress" => Crypto::EcdsaPkDecompress(curve),
+            "ecdsa_pk_recover" => Crypto::EcdsaPkRecover(curve),
+            "vrf_verify" => Crypto::VrfVerify,
+            _ => return None,
+        })
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Crypto::Sha256
+            | Crypto::Keccak256
+            | Crypto::Sha512_256
+            | Crypto::Sha3_256
+            | Crypto::EcdsaPkDecompress(_) => 1,
+            Crypto::Ed25519Verify | Crypto::Ed25519VerifyBare | Crypto::VrfVerify => 3,
+            Crypto::EcdsaPkRecover(_) => 4,
+            Crypto::EcdsaVerify(_) => 5,
+        }
+    }
+
+    fn opcode(&self) -> String {
+        match self {
+            Crypto::Sha256 => "sha256".to_string(),
+            Crypto::Keccak256 => "keccak256".to_string(),
+            Crypto::Sha512_256 => "sha512_256".to_string(),
+            Crypto::Sha3_256 => "sha3_256".to_string(),
+            Crypto::Ed25519Verify => "ed25519verify".to_string(),
+            Crypto::Ed25519VerifyBare => "ed25519verify_bare".to_string(),
+            Crypto::EcdsaVerify(curve) => format!("ecdsa_verify {curve:?}"),
+            Crypto::EcdsaPkDecompress(curve) => format!("ecdsa_pk_decompress {curve:?}"),
+            Crypto::EcdsaPkRecover(curve) => format!("ecdsa_pk_recover {curve:?}"),
+            Crypto::VrfVerify => "vrf_verify VrfAlgorand".to_string(),
+        }
+    }
+
+    fn check(&self, context: &CompilationContext) -> Result<(), CompilationError> {
+        let opcode = self.opcode();
+        match self {
+            Crypto::Sha256 | Crypto::Keccak256 | Crypto::Sha512_256 => Ok(()),
+            // ed25519verify was restricted to logic signatures before v5
+            Crypto::Ed25519Verify if context.version < 5 => {
+                context.require_mode(&opcode, Mode::Signature)
+            }
+            Crypto::Ed25519Verify => Ok(()),
+            Crypto::EcdsaVerify(curve)
+            | Crypto::EcdsaPkDecompress(curve)
+            | Crypto::EcdsaPkRecover(curve) => context.require_version(
+                &opcode,
+                match curve {
+                    Curve::Secp256k1 => 5,
+                    Curve::Secp256r1 => 7,
+                },
+            ),
+            Crypto::Sha3_256 | Crypto::Ed25519VerifyBare | Crypto::VrfVerify => {
+                context.require_version(&opcode, 7)
+            }
+        }
+    }
+}
+
+impl Expression for Crypto {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let public_key = TypeEnum::Tuple(vec![typesig!(bytes), typesig!(bytes)]);
+        Ok(match self {
+            Crypto::Sha256 | Crypto::Keccak256 | Crypto::Sha512_256 | Crypto::Sha3_256 => {
+                typesig!(bytes -> bytes)
+            }
+            Crypto::EcdsaPkDecompress(_) => typesig!(bytes -> #public_key),
+            Crypto::Ed25519Verify | Crypto::Ed25519VerifyBare => {
+                typesig!(bytes -> bytes -> bytes -> int)
+            }
+            Crypto::EcdsaVerify(_) => typesig!(bytes -> bytes -> bytes -> bytes -> bytes -> int),
+            Crypto::EcdsaPkRecover(_) => typesig!(bytes -> int -> bytes -> bytes -> #public_key),
+            Crypto::VrfVerify => {
+                let verified_output = TypeEnum::Tuple(vec![typesig!(bytes), typesig!(int)]);
+                typesig!(bytes -> bytes -> bytes -> #verified_output)
+            }
+        })
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        self.check(context)?;
+        let mut pieces = pop_args(prepared_stack, self.arity())?;
+        pieces.push(self.opcode());
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply, bytes,
+        context::{CompilationContext, Mode, TypeContext},
+        expression::{
+            apply::Apply,
+            crypto::{Crypto, Curve},
+            primitive::Primitive,
+            Expr, Expression,
+        },
+    };
+
+    #[test]
+    fn from_name() {
+        assert_eq!(
+            Crypto::from_name("ecdsa_verify_secp256r1"),
+            Some(Crypto::EcdsaVerify(Curve::Secp256r1))
+        );
+        assert_eq!(
+            Crypto::from_name("ecdsa_verify"),
+            Some(Crypto::EcdsaVerify(Curve::Secp256k1))
+        );
+        assert_eq!(Crypto::from_name("sha256_secp256r1"), None);
+        assert_eq!(Crypto::from_name("vrf_verify_secp256r1"), None);
+    }
+
+    #[test]
+    fn hash() {
+        let e = apply!(@fn Expr::Crypto(Crypto::Sha512_256); @arg bytes!("commit".into()));
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        assert_eq!(e.compile_raw().unwrap(), "byte \"commit\"\nsha512_256");
+    }
+
+    #[test]
+    fn version_and_mode() {
+        let e = apply!(
+            @fn Expr::Crypto(Crypto::Ed25519Verify);
+            @arg bytes!("data".into());
+            @arg bytes!("sig".into());
+            @arg bytes!("key".into());
+        );
+        e.resolve(&TypeContext::default()).unwrap();
+        assert!(e.compile_raw().is_ok());
+
+        let v4 = CompilationContext {
+            version: 4,
+            ..Default::default()
+        };
+        assert!(e.compile(&v4, &mut vec![]).is_err());
+        let v4_signature = CompilationContext {
+            version: 4,
+            mode: Mode::Signature,
+            ..Default::default()
+        };
+        assert!(e.compile(&v4_signature, &mut vec![]).is_ok());
+
+        let v6 = CompilationContext {
+            version: 6,
+            ..Default::default()
+        };
+        let e = apply!(@fn Expr::Crypto(Crypto::Sha3_256); @arg bytes!("data".into()));
+        assert!(e.compile(&v6, &mut vec![]).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use strum_macros::EnumString;
+
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+};
+
+use super::{Expression, Prepared};
+
+/// Fields of the `global` opcode, e.g. `Global.Round`.
+///
+/// Not to be confused with `global.field`, which refers to application state
+/// declared in the `schema global` block (see [`super::var::Var::Global`]).
+#[derive(Debug, Clone, PartialEq, EnumString)]
+pub enum Global {
+    MinTxnFee,
+    MinBalance,
+    MaxTxnLife,
+    ZeroAddress,
+    GroupSize,
+    LogicSigVersion,
+    Round,
+    LatestTimestamp,
+    CurrentApplicationID,
+    CreatorAddress,
+    CurrentApplicationAddress,
+    GroupID,
+    OpcodeBudget,
+    CallerApplicationID,
+    CallerApplicationAddress,
+    AssetCreateMinBalance,
+    AssetOptInMinBalance,
+    GenesisHash,
+}
+
+impl Global {
+    pub fn field_type(&self) -> TypeEnum {
+        match self {
+            Global::ZeroAddress
+            | Global::CreatorAddress
+            | Global::CurrentApplicationAddress
+            | Global::GroupID
+            | Global::CallerApplicationAddress
+            | Global::GenesisHash => typesig!(bytes),
+            _ => typesig!(int),
+        }
+    }
+
+    /// Minimum TEAL version in which this field is available.
+    pub fn version(&self) -> u64 {
+        match self {
+            Global::AssetCreateMinBalance | Global::AssetOptInMinBalance | Global::GenesisHash => {
+                10
+            }
+            Global::OpcodeBudget
+            | Global::CallerApplicationID
+            | Global::CallerApplicationAddress => 6,
+            Global::CurrentApplicationAddress | Global::GroupID => 5,
+            Global::CreatorAddress => 3,
+            Global::LogicSigVersion
+            | Global::Round
+            | Global::LatestTimestamp
+            | Global::CurrentApplicationID => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl Expression for Global {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        Ok(self.field_type())
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        _: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        context.require_version(&format!("Global field {self:?}"), self.version())?;
+        Ok(format!("global {self:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        context::{CompilationContext, TypeContext},
+        expression::{global::Global, Expression},
+        typing::{TypeEnum, TypePrimitive},
+    };
+
+    #[test]
+    fn test() {
+        assert_eq!(
+            Global::Round.resolve(&TypeContext::default()).unwrap(),
+            TypeEnum::Simple(TypePrimitive::UInt64)
+        );
+        assert_eq!(
+            Global::ZeroAddress
+                .resolve(&TypeContext::default())
+                .unwrap(),
+            TypeEnum::Simple(TypePrimitive::Byteslice)
+        );
+        assert_eq!(Global::Round.compile_raw().unwrap(), "global Round");
+    }
+
+    #[test]
+    fn version() {
+        let context = CompilationContext {
+            version: 5,
+            ..Default::default()
+        };
+        assert!(Global::GroupID.compile(&context, &mut vec![]).is_ok());
+        assert!(Global::OpcodeBudget.compile(&context, &mut vec![]).is_err());
+
+        let context = CompilationContext {
+            version: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            Global::GenesisHash.compile(&context, &mut vec![]).unwrap(),
+            "global GenesisHash"
+        );
+        assert_eq!(
+            Global::GenesisHash
+                .resolve(&TypeContext::default())
+                .unwrap(),
+            TypeEnum::Simple(TypePrimitive::Byteslice)
+        );
+        assert_eq!(
+            Global::AssetOptInMinBalance
+                .resolve(&TypeContext::default())
+                .unwrap(),
+            TypeEnum::Simple(TypePrimitive::UInt64)
+        );
+        let context = CompilationContext {
+            version: 9,
+            ..Default::default()
+        };
+        assert!(Global::AssetCreateMinBalance
+            .compile(&context, &mut vec![])
+            .is_err());
+    }
+}
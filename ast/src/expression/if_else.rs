@@ -7,7 +7,7 @@ use crate::{
     OP_SEPARATOR,
 };
 
-use super::{Expr, Expression};
+use super::{pop_arg, Expr, Expression, Prepared};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct If(pub Expr, pub Expr);
@@ -24,15 +24,15 @@ impl Expression for If {
     fn compile(
         &self,
         context: &CompilationContext,
-        prepared_stack: &mut Vec<String>,
+        prepared_stack: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
         let If(true_expression, false_expression) = self;
         let true_compiled = true_expression.compile(context, &mut vec![])?;
         let false_compiled = false_expression.compile(context, &mut vec![])?;
         let else_label_id = format!("else{}", create_label_id());
         let endif_label_id = format!("endif{}", create_label_id());
-        Ok(vec![
-            prepared_stack.pop().ok_or(CompilationError::MissingStack)?,
+        Ok([
+            pop_arg(prepared_stack)?.compiled,
             format!("bz {else_label_id}"),
             true_compiled,
             format!("b {endif_label_id}"),
@@ -49,7 +49,10 @@ mod tests {
     use crate::{
         apply, binop, bytes,
         context::TypeContext,
-        expression::{apply::Apply, binary::Binary, primitive::Primitive, Expr, Expression},
+        expression::{
+            apply::Apply, binary::Binary, operator::Operator, primitive::Primitive, Expr,
+            Expression,
+        },
         int,
     };
 
@@ -0,0 +1,244 @@
+use strum_macros::EnumString;
+
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, Mode, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{txn::Txn, Expr, Expression, Prepared};
+
+/// Transaction types that may be issued as inner transactions.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum TxnType {
+    Pay,
+    Keyreg,
+    Acfg,
+    Axfer,
+    Afrz,
+    Appl,
+}
+
+impl TxnType {
+    /// Whether `field` may be set on an inner transaction of this type.
+    pub fn allows(&self, field: &Txn) -> bool {
+        use Txn::*;
+
+        match field {
+            Sender | Fee | Note | RekeyTo => true,
+            Receiver | Amount | CloseRemainderTo => *self == TxnType::Pay,
+            VotePK | SelectionPK | VoteFirst | VoteLast | VoteKeyDilution | Nonparticipation
+            | StateProofPK => *self == TxnType::Keyreg,
+            ConfigAsset
+            | ConfigAssetTotal
+            | ConfigAssetDecimals
+            | ConfigAssetDefaultFrozen
+            | ConfigAssetUnitName
+            | ConfigAssetName
+            | ConfigAssetURL
+            | ConfigAssetMetadataHash
+            | ConfigAssetManager
+            | ConfigAssetReserve
+            | ConfigAssetFreeze
+            | ConfigAssetClawback => *self == TxnType::Acfg,
+            XferAsset | AssetAmount | AssetSender | AssetReceiver | AssetCloseTo => {
+                *self == TxnType::Axfer
+            }
+            FreezeAsset | FreezeAssetAccount | FreezeAssetFrozen => *self == TxnType::Afrz,
+            ApplicationID
+            | OnCompletion
+            | ApplicationArgs
+            | Accounts
+            | Assets
+            | Applications
+            | ApprovalProgram
+            | ClearStateProgram
+            | GlobalNumUint
+            | GlobalNumByteSlice
+            | LocalNumUint
+            | LocalNumByteSlice
+            | ExtraProgramPages
+            | ApprovalProgramPages
+            | ClearStateProgramPages => *self == TxnType::Appl,
+            _ => false,
+        }
+    }
+
+    /// Minimum TEAL version in which inner transactions of this type can be
+    /// issued.
+    pub fn version(&self) -> u64 {
+        match self {
+            TxnType::Pay | TxnType::Acfg | TxnType::Axfer | TxnType::Afrz => 5,
+            TxnType::Keyreg | TxnType::Appl => 6,
+        }
+    }
+}
+
+/// An inner transaction described as a record, e.g.
+/// `pay { receiver: Txn.Sender, amount: 1000 }`. Only the fields given are
+/// set, so protocol defaults (such as the minimum fee being paid by the
+/// application) apply unless overridden, e.g. with `fee: 0`. Array fields
+/// appear once per element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InnerTxn {
+    pub kind: TxnType,
+    pub fields: Vec<(Txn, Expr)>,
+}
+
+impl InnerTxn {
+    fn compile_fields(
+        &self,
+        context: &CompilationContext,
+    ) -> Result<Vec<String>, CompilationError> {
+        context.require_version(
+            &format!("Inner {:?} transaction", self.kind),
+            self.kind.version(),
+        )?;
+        let mut pieces = vec![
+            format!("int {kind:?}", kind = self.kind).to_lowercase(),
+            "itxn_field TypeEnum".to_string(),
+        ];
+        for (field, value) in &self.fields {
+            context.require_version(&format!("Transaction field {field:?}"), field.version())?;
+            pieces.push(value.compile(context, &mut vec![])?);
+            pieces.push(format!("itxn_field {field:?}"));
+        }
+        Ok(pieces)
+    }
+}
+
+impl Expression for InnerTxn {
+    fn resolve(&self, context: &TypeContext) -> Result<TypeEnum, TypeError> {
+        for (i, (field, value)) in self.fields.iter().enumerate() {
+            if !self.kind.allows(field) {
+                return Err(TypeError::InvalidInnerTxnField(self.kind, field.clone()));
+            }
+            if !field.is_array() && self.fields[..i].iter().any(|(f, _)| f == field) {
+                return Err(TypeError::DuplicateInnerTxnField(field.clone()));
+            }
+            value.resolve(context)?.unify(&mut field.field_type())?;
+        }
+        Ok(typesig!(void))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        _: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        InnerTxnGroup(vec![self.clone()]).compile(context, &mut vec![])
+    }
+}
+
+/// Inner transactions submitted together as a group, e.g.
+/// `group { pay { ... }, appl { ... } }`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InnerTxnGroup(pub Vec<InnerTxn>);
+
+impl Expression for InnerTxnGroup {
+    fn resolve(&self, context: &TypeContext) -> Result<TypeEnum, TypeError> {
+        for txn in &self.0 {
+            txn.resolve(context)?;
+        }
+        Ok(typesig!(void))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        _: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        context.require_mode("Inner transactions", Mode::Application)?;
+        context.require_version("Inner transactions", 5)?;
+        if self.0.len() > 1 {
+            context.require_version("itxn_next", 6)?;
+        }
+        let mut pieces = vec![];
+        for (i, txn) in self.0.iter().enumerate() {
+            pieces.push(if i == 0 { "itxn_begin" } else { "itxn_next" }.to_string());
+            pieces.extend(txn.compile_fields(context)?);
+        }
+        pieces.push("itxn_submit".to_string());
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bytes,
+        context::{CompilationContext, TypeContext},
+        expression::{primitive::Primitive, txn::Txn, Expr, Expression},
+        int,
+    };
+
+    use super::{InnerTxn, InnerTxnGroup, TxnType};
+
+    #[test]
+    fn payment() {
+        let e = InnerTxn {
+            kind: TxnType::Pay,
+            fields: vec![
+                (Txn::Receiver, Expr::Txn(Txn::Sender)),
+                (Txn::Amount, int!(1000)),
+                (Txn::Fee, int!(0)),
+            ],
+        };
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "itxn_begin\nint pay\nitxn_field TypeEnum\ntxn Sender\nitxn_field Receiver\nint 1000\nitxn_field Amount\nint 0\nitxn_field Fee\nitxn_submit"
+        );
+    }
+
+    #[test]
+    fn field_table() {
+        let e = InnerTxn {
+            kind: TxnType::Pay,
+            fields: vec![(Txn::XferAsset, int!(1))],
+        };
+        assert!(e.resolve(&TypeContext::default()).is_err());
+
+        let e = InnerTxn {
+            kind: TxnType::Pay,
+            fields: vec![(Txn::Amount, bytes!("1".into()))],
+        };
+        assert!(e.resolve(&TypeContext::default()).is_err());
+
+        let e = InnerTxn {
+            kind: TxnType::Appl,
+            fields: vec![
+                (Txn::ApplicationID, int!(1)),
+                (Txn::ApplicationArgs, bytes!("a".into())),
+                (Txn::ApplicationArgs, bytes!("b".into())),
+            ],
+        };
+        assert!(e.resolve(&TypeContext::default()).is_ok());
+    }
+
+    #[test]
+    fn group() {
+        let e = InnerTxnGroup(vec![
+            InnerTxn {
+                kind: TxnType::Pay,
+                fields: vec![(Txn::Amount, int!(1))],
+            },
+            InnerTxn {
+                kind: TxnType::Appl,
+                fields: vec![(Txn::ApplicationID, int!(2))],
+            },
+        ]);
+        e.resolve(&TypeContext::default()).unwrap();
+        let compiled = e.compile_raw().unwrap();
+        assert!(compiled.contains("itxn_next\nint appl\nitxn_field TypeEnum"));
+
+        let v5 = CompilationContext {
+            version: 5,
+            ..Default::default()
+        };
+        assert!(e.compile(&v5, &mut vec![]).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    abi::{encode_tuple, AbiType},
+    compilation_error::CompilationError,
+    context::{CompilationContext, Mode, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{pop_args, primitive::Primitive, Expr, Expression, Prepared};
+
+/// `log(value)`, appending `value` to the application call's logs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Log;
+
+impl Expression for Log {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        Ok(typesig!(bytes -> void))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        context.require_mode("log", Mode::Application)?;
+        context.require_version("log", 5)?;
+        let mut pieces = pop_args(prepared_stack, 1)?;
+        pieces.push("log".to_string());
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+/// `emit Name(args...)`, logging an event declared in the contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Emit {
+    pub event: String,
+    pub args: Vec<Expr>,
+}
+
+impl Expression for Emit {
+    fn resolve(&self, context: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let event = context
+            .events
+            .iter()
+            .find(|e| e.name == self.event)
+            .ok_or_else(|| TypeError::UnknownEvent(self.event.clone()))?;
+        if event.args.len() != self.args.len() {
+            return Err(TypeError::WrongArgumentCount(
+                self.event.clone(),
+                event.args.len(),
+                self.args.len(),
+            ));
+        }
+        for ((_, abi_type), arg) in event.args.iter().zip(&self.args) {
+            arg.resolve(context)?.unify(&mut abi_type.stack_type())?;
+        }
+        Ok(typesig!(void))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        _: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let event = context
+            .events
+            .iter()
+            .find(|e| e.name == self.event)
+            .ok_or_else(|| TypeError::UnknownEvent(self.event.clone()))?;
+        let types = event
+            .args
+            .iter()
+            .map(|(_, abi_type)| abi_type.clone())
+            .collect::<Vec<AbiType>>();
+        let mut pieces =
+            vec![Primitive::Byteslice(event.selector().to_vec()).compile(context, &mut vec![])?];
+        pieces.extend(encode_tuple(context, &types, &self.args)?);
+        pieces.push("concat".to_string());
+        Log.compile(context, &mut vec![pieces.join(OP_SEPARATOR).into()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{
+        abi::AbiType,
+        bytes,
+        context::{CompilationContext, TypeContext},
+        event::Event,
+        expression::{primitive::Primitive, txn::Txn, Expr, Expression},
+        int,
+    };
+
+    use super::Emit;
+
+    fn transfer() -> Vec<Event> {
+        vec![Event {
+            name: "Transfer".to_string(),
+            args: vec![
+                ("from".to_string(), AbiType::Address),
+                ("to".to_string(), AbiType::Address),
+                ("amount".to_string(), AbiType::Uint(64)),
+            ],
+            doc: None,
+        }]
+    }
+
+    #[test]
+    fn test() {
+        let events = transfer();
+        assert_eq!(events[0].signature(), "Transfer(address,address,uint64)");
+        let e = Emit {
+            event: "Transfer".to_string(),
+            args: vec![
+                Expr::Txn(Txn::Sender),
+                Expr::Txn(Txn::Receiver),
+                Expr::Txn(Txn::Amount),
+            ],
+        };
+        println!(
+            "{}",
+            e.resolve(&TypeContext {
+                events: Rc::new(events.clone()),
+                ..Default::default()
+            })
+            .unwrap()
+        );
+        let compiled = e
+            .compile(
+                &CompilationContext {
+                    events: &events,
+                    ..Default::default()
+                },
+                &mut vec![],
+            )
+            .unwrap();
+        println!("{compiled}");
+        assert!(compiled.ends_with("txn Amount\nitob\nconcat\nswap\nconcat\nconcat\nlog"));
+
+        let wrong = Emit {
+            event: "Transfer".to_string(),
+            args: vec![int!(1), bytes!("to".into()), int!(1)],
+        };
+        assert!(wrong
+            .resolve(&TypeContext {
+                events: Rc::new(events),
+                ..Default::default()
+            })
+            .is_err());
+        assert!(e.resolve(&TypeContext::default()).is_err());
+    }
+}
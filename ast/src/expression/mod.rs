@@ -4,12 +4,24 @@ use crate::{
     typing::{TypeEnum, TypeError},
 };
 
+pub mod app_state;
 pub mod apply;
+pub mod arg;
+pub mod arithmetic;
 pub mod binary;
 pub mod bind;
+pub mod box_op;
+pub mod byte_op;
 pub mod cond;
 pub mod constant;
+pub mod crypto;
+pub mod global;
 pub mod if_else;
+pub mod inner_txn;
+pub mod log;
+pub mod operator;
+pub mod option;
+pub mod params;
 pub mod primitive;
 pub mod ret;
 pub mod seq;
@@ -19,16 +31,41 @@ pub mod var;
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Apply(Box<apply::Apply>),
+    AppStateEx(app_state::AppStateEx),
+    Arithmetic(arithmetic::Arithmetic),
+    Balance(params::Balance),
+    AssetHolding(params::AssetHolding),
+    AssetParams(params::AssetParams),
+    AppParams(params::AppParams),
+    AcctParams(params::AcctParams),
     Binary(binary::Binary),
+    Operator(operator::Operator),
+    OptionOp(option::OptionOp),
+    Match(Box<option::Match>),
     Bind(Box<bind::Bind>),
+    BoxOp(box_op::BoxOp),
+    ByteOp(byte_op::ByteOp),
     Cond(Box<cond::Cond>),
     OnComplete(constant::OnComplete),
+    Crypto(crypto::Crypto),
+    Global(global::Global),
     If(Box<if_else::If>),
+    Arg(arg::Arg),
+    Log(log::Log),
+    Emit(Box<log::Emit>),
     Primitive(primitive::Primitive),
     Ret(ret::Ret),
     Seq(Box<seq::Seq>),
     Txn(txn::Txn),
+    Txna(txn::Txna),
+    Gtxn(txn::Gtxn),
+    Itxn(txn::Itxn),
+    Gitxn(txn::Gitxn),
+    InnerTxn(Box<inner_txn::InnerTxn>),
+    InnerTxnGroup(Box<inner_txn::InnerTxnGroup>),
     LVal(var::LVal),
+    Delete(var::Delete),
+    Exists(var::Exists),
     RVal(var::RVal),
 }
 
@@ -37,7 +74,7 @@ pub trait Expression {
     fn compile(
         &self,
         context: &CompilationContext,
-        prepared_stack: &mut Vec<String>,
+        prepared_stack: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError>;
 
     fn compile_raw(&self) -> Result<String, CompilationError> {
@@ -45,20 +82,109 @@ pub trait Expression {
     }
 }
 
+/// An argument compiled by [`apply::Apply`], together with its value when the
+/// argument is a literal, so that functions can use the immediate forms of
+/// their opcodes and check sizes at compile time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prepared {
+    pub compiled: String,
+    pub constant: Option<primitive::Primitive>,
+}
+
+impl Prepared {
+    pub fn uint64(&self) -> Option<u64> {
+        match self.constant {
+            Some(primitive::Primitive::UInt64(i)) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Value of a literal that fits the single-byte immediate arguments of
+    /// most opcodes; larger constants must stay on the stack.
+    pub fn immediate(&self) -> Option<u64> {
+        self.uint64().filter(|i| *i <= u8::MAX as u64)
+    }
+
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match &self.constant {
+            Some(primitive::Primitive::Byteslice(bytes)) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for Prepared {
+    fn from(compiled: String) -> Self {
+        Prepared {
+            compiled,
+            constant: None,
+        }
+    }
+}
+
+/// Pops the next argument of a function, in application order.
+pub(crate) fn pop_arg(prepared_stack: &mut Vec<Prepared>) -> Result<Prepared, CompilationError> {
+    prepared_stack.pop().ok_or(CompilationError::MissingStack)
+}
+
+/// Pops the arguments of an `n`-ary function, in application order.
+pub(crate) fn pop_prepared(
+    prepared_stack: &mut Vec<Prepared>,
+    n: usize,
+) -> Result<Vec<Prepared>, CompilationError> {
+    (0..n).map(|_| pop_arg(prepared_stack)).collect()
+}
+
+/// Pops the compiled arguments of an `n`-ary function, in application order.
+pub(crate) fn pop_args(
+    prepared_stack: &mut Vec<Prepared>,
+    n: usize,
+) -> Result<Vec<String>, CompilationError> {
+    Ok(pop_prepared(prepared_stack, n)?
+        .into_iter()
+        .map(|arg| arg.compiled)
+        .collect())
+}
+
 impl Expression for Expr {
     fn resolve(&self, context: &TypeContext) -> Result<TypeEnum, TypeError> {
         match self {
             Expr::Apply(expr) => expr.resolve(context),
+            Expr::AppStateEx(expr) => expr.resolve(context),
+            Expr::Arithmetic(expr) => expr.resolve(context),
+            Expr::Balance(expr) => expr.resolve(context),
+            Expr::AssetHolding(expr) => expr.resolve(context),
+            Expr::AssetParams(expr) => expr.resolve(context),
+            Expr::AppParams(expr) => expr.resolve(context),
+            Expr::AcctParams(expr) => expr.resolve(context),
             Expr::Binary(expr) => expr.resolve(context),
+            Expr::Operator(expr) => expr.resolve(context),
+            Expr::OptionOp(expr) => expr.resolve(context),
+            Expr::Match(expr) => expr.resolve(context),
             Expr::Bind(expr) => expr.resolve(context),
+            Expr::BoxOp(expr) => expr.resolve(context),
+            Expr::ByteOp(expr) => expr.resolve(context),
             Expr::Cond(expr) => expr.resolve(context),
             Expr::OnComplete(expr) => expr.resolve(context),
+            Expr::Crypto(expr) => expr.resolve(context),
+            Expr::Global(expr) => expr.resolve(context),
             Expr::If(expr) => expr.resolve(context),
+            Expr::Arg(expr) => expr.resolve(context),
+            Expr::Log(expr) => expr.resolve(context),
+            Expr::Emit(expr) => expr.resolve(context),
             Expr::Primitive(expr) => expr.resolve(context),
             Expr::Ret(expr) => expr.resolve(context),
             Expr::Seq(expr) => expr.resolve(context),
             Expr::Txn(expr) => expr.resolve(context),
+            Expr::Txna(expr) => expr.resolve(context),
+            Expr::Gtxn(expr) => expr.resolve(context),
+            Expr::Itxn(expr) => expr.resolve(context),
+            Expr::Gitxn(expr) => expr.resolve(context),
+            Expr::InnerTxn(expr) => expr.resolve(context),
+            Expr::InnerTxnGroup(expr) => expr.resolve(context),
             Expr::LVal(expr) => expr.resolve(context),
+            Expr::Delete(expr) => expr.resolve(context),
+            Expr::Exists(expr) => expr.resolve(context),
             Expr::RVal(expr) => expr.resolve(context),
         }
     }
@@ -66,20 +192,45 @@ impl Expression for Expr {
     fn compile(
         &self,
         context: &CompilationContext,
-        prepared_stack: &mut Vec<String>,
+        prepared_stack: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
         match self {
             Expr::Apply(expr) => expr.compile(context, prepared_stack),
+            Expr::AppStateEx(expr) => expr.compile(context, prepared_stack),
+            Expr::Arithmetic(expr) => expr.compile(context, prepared_stack),
+            Expr::Balance(expr) => expr.compile(context, prepared_stack),
+            Expr::AssetHolding(expr) => expr.compile(context, prepared_stack),
+            Expr::AssetParams(expr) => expr.compile(context, prepared_stack),
+            Expr::AppParams(expr) => expr.compile(context, prepared_stack),
+            Expr::AcctParams(expr) => expr.compile(context, prepared_stack),
             Expr::Binary(expr) => expr.compile(context, prepared_stack),
+            Expr::Operator(expr) => expr.compile(context, prepared_stack),
+            Expr::OptionOp(expr) => expr.compile(context, prepared_stack),
+            Expr::Match(expr) => expr.compile(context, prepared_stack),
             Expr::Bind(expr) => expr.compile(context, prepared_stack),
+            Expr::BoxOp(expr) => expr.compile(context, prepared_stack),
+            Expr::ByteOp(expr) => expr.compile(context, prepared_stack),
             Expr::Cond(expr) => expr.compile(context, prepared_stack),
             Expr::If(expr) => expr.compile(context, prepared_stack),
             Expr::OnComplete(expr) => expr.compile(context, prepared_stack),
+            Expr::Crypto(expr) => expr.compile(context, prepared_stack),
+            Expr::Global(expr) => expr.compile(context, prepared_stack),
+            Expr::Arg(expr) => expr.compile(context, prepared_stack),
+            Expr::Log(expr) => expr.compile(context, prepared_stack),
+            Expr::Emit(expr) => expr.compile(context, prepared_stack),
             Expr::Primitive(expr) => expr.compile(context, prepared_stack),
             Expr::Ret(expr) => expr.compile(context, prepared_stack),
             Expr::Seq(expr) => expr.compile(context, prepared_stack),
             Expr::Txn(expr) => expr.compile(context, prepared_stack),
+            Expr::Txna(expr) => expr.compile(context, prepared_stack),
+            Expr::Gtxn(expr) => expr.compile(context, prepared_stack),
+            Expr::Itxn(expr) => expr.compile(context, prepared_stack),
+            Expr::Gitxn(expr) => expr.compile(context, prepared_stack),
+            Expr::InnerTxn(expr) => expr.compile(context, prepared_stack),
+            Expr::InnerTxnGroup(expr) => expr.compile(context, prepared_stack),
             Expr::LVal(expr) => expr.compile(context, prepared_stack),
+            Expr::Delete(expr) => expr.compile(context, prepared_stack),
+            Expr::Exists(expr) => expr.compile(context, prepared_stack),
             Expr::RVal(expr) => expr.compile(context, prepared_stack),
         }
     }
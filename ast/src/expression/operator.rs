@@ -0,0 +1,164 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+};
+
+use super::{
+    binary::{op, Binary},
+    pop_arg, Expression, Prepared,
+};
+
+/// A binary operator overloaded on the type of its operands: `+`, `*`, `<`,
+/// etc. compile to the uint64 opcodes for `int` operands and to the byte math
+/// opcodes (`b+`, `b*`, `b<`, ...) for `biguint` operands.
+///
+/// The operand type is recorded while resolving and must be `int` or
+/// `biguint`, or `bytes` for `==` and `!=`. The expression must be type
+/// checked before it is compiled.
+#[derive(Debug, Clone)]
+pub struct Operator {
+    pub op: Binary,
+    pub operand: TypeVar,
+}
+
+impl Operator {
+    pub fn new(op: Binary) -> Self {
+        Self {
+            op,
+            operand: TypeVar::new(),
+        }
+    }
+}
+
+impl Operator {
+    /// Rejects operand types the operator is not overloaded on, once the
+    /// operand has been unified with an argument.
+    pub(crate) fn check_operand(&self) -> Result<(), TypeError> {
+        let operand = TypeEnum::Var(self.operand.clone()).resolved();
+        match operand {
+            TypeEnum::Var(_)
+            | TypeEnum::Simple(
+                TypePrimitive::UInt64 | TypePrimitive::BigUInt | TypePrimitive::Halt,
+            ) => Ok(()),
+            TypeEnum::Simple(TypePrimitive::Byteslice)
+                if matches!(self.op, Binary::Equals | Binary::NotEquals) =>
+            {
+                Ok(())
+            }
+            _ => Err(TypeError::IrreconcilableTypes(operand, typesig!(int))),
+        }
+    }
+}
+
+impl PartialEq for Operator {
+    fn eq(&self, other: &Self) -> bool {
+        self.op == other.op
+    }
+}
+
+impl Expression for Operator {
+    fn resolve(&self, context: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let a = TypeEnum::Var(self.operand.clone());
+        let b = a.clone();
+        Ok(match self.op {
+            Binary::And | Binary::Or => self.op.resolve(context)?,
+            Binary::Add | Binary::Subtract | Binary::Multiply | Binary::Divide | Binary::Modulo => {
+                let result = a.clone();
+                typesig!(#a -> #b -> #result)
+            }
+            _ => typesig!(#a -> #b -> int),
+        })
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        if matches!(self.op, Binary::And | Binary::Or) {
+            return self.op.compile(context, prepared_stack);
+        }
+        let operand = TypeEnum::Var(self.operand.clone()).resolved();
+        match (&operand, self.op.byte_math_opcode()) {
+            (TypeEnum::Simple(TypePrimitive::BigUInt), Some(opcode)) => {
+                context.require_version("Byte math", 4)?;
+                let b = pop_arg(prepared_stack)?.compiled;
+                let a = pop_arg(prepared_stack)?.compiled;
+                op(a, opcode, b)
+            }
+            (TypeEnum::Var(_), _) => Err(CompilationError::UnresolvedOperand(self.op.clone())),
+            _ => {
+                self.check_operand()?;
+                self.op.compile(context, prepared_stack)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply, binop, bytes,
+        compilation_error::CompilationError,
+        context::TypeContext,
+        expression::{
+            apply::Apply, arithmetic::Arithmetic, binary::Binary, operator::Operator,
+            primitive::Primitive, Expr, Expression,
+        },
+        int,
+        typing::TypeError,
+    };
+
+    fn biguint(e: Expr) -> Expr {
+        apply!(@fn Expr::Arithmetic(Arithmetic::ToBigUInt); @arg e)
+    }
+
+    #[test]
+    fn uint64() {
+        let e = binop!((int!(2)) * (int!(3)));
+        e.resolve(&TypeContext::default()).unwrap();
+        assert_eq!(e.compile_raw().unwrap(), "int 2\nint 3\n*");
+    }
+
+    #[test]
+    fn biguint_overload() {
+        let e = binop!((biguint(int!(2))) * (biguint(int!(3))));
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        assert_eq!(e.compile_raw().unwrap(), "int 2\nitob\nint 3\nitob\nb*");
+
+        let e = binop!((biguint(int!(2))) < (biguint(int!(3))));
+        e.resolve(&TypeContext::default()).unwrap();
+        assert!(e.compile_raw().unwrap().ends_with("b<"));
+    }
+
+    #[test]
+    fn mixed_operands() {
+        let e = binop!((biguint(int!(2))) + (int!(3)));
+        assert!(e.resolve(&TypeContext::default()).is_err());
+    }
+
+    #[test]
+    fn byteslice_operands() {
+        let e = binop!((bytes!("a".into())) + (bytes!("b".into())));
+        println!("{:?}", e.resolve(&TypeContext::default()).err());
+        assert!(matches!(
+            e.resolve(&TypeContext::default()),
+            Err(TypeError::IrreconcilableTypes(..))
+        ));
+
+        let e = binop!((bytes!("a".into())) == (bytes!("b".into())));
+        e.resolve(&TypeContext::default()).unwrap();
+        assert!(e.compile_raw().unwrap().ends_with("=="));
+    }
+
+    #[test]
+    fn unresolved_operand() {
+        let e = binop!((int!(2)) * (int!(3)));
+        assert!(matches!(
+            e.compile_raw(),
+            Err(CompilationError::UnresolvedOperand(Binary::Multiply))
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationBinding, CompilationContext, TypeContext},
+    label::create_label_id,
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{pop_args, Expr, Expression, Prepared};
+
+/// Built-in functions consuming an optional value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionOp {
+    /// `is_some(o)`, discarding the value and keeping the existence flag
+    IsSome,
+    /// `unwrap_or(o, default)`, evaluating `default` only if `o` is empty
+    UnwrapOr,
+}
+
+impl OptionOp {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "is_some" => OptionOp::IsSome,
+            "unwrap_or" => OptionOp::UnwrapOr,
+            _ => return None,
+        })
+    }
+}
+
+impl Expression for OptionOp {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let value = TypeEnum::Var(TypeVar::new());
+        let option = TypeEnum::Option(Box::new(value.clone()));
+        Ok(match self {
+            OptionOp::IsSome => typesig!(#option -> int),
+            OptionOp::UnwrapOr => {
+                let default = value.clone();
+                typesig!(#option -> #default -> #value)
+            }
+        })
+    }
+
+    fn compile(
+        &self,
+        _: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        Ok(match self {
+            OptionOp::IsSome => {
+                let [option] = &pop_args(prepared_stack, 1)?[..] else {
+                    unreachable!()
+                };
+                [option.as_str(), "swap", "pop"].join(OP_SEPARATOR)
+            }
+            OptionOp::UnwrapOr => {
+                let [option, default] = &pop_args(prepared_stack, 2)?[..] else {
+                    unreachable!()
+                };
+                let label_id = format!("unwrap{}", create_label_id());
+                [
+                    option.clone(),
+                    format!("bnz {label_id}"),
+                    "pop".to_string(),
+                    default.clone(),
+                    format!("{label_id}:"),
+                ]
+                .join(OP_SEPARATOR)
+            }
+        })
+    }
+}
+
+/// `match value { Some(identifier) => some, None => none }`, binding the
+/// contained value to `identifier` in the `some` branch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub value: Expr,
+    pub identifier: String,
+    pub some: Expr,
+    pub none: Expr,
+}
+
+impl Expression for Match {
+    fn resolve(&self, context: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let Match {
+            value,
+            identifier,
+            some,
+            none,
+        } = self;
+        let mut inner = TypeEnum::Var(TypeVar::new());
+        value
+            .resolve(context)?
+            .unify(&mut TypeEnum::Option(Box::new(inner.clone())))?;
+        let some_context = context.with_binds(vec![(identifier.to_string(), inner.resolved())]);
+        inner = some.resolve(&some_context)?;
+        inner.unify(&mut none.resolve(context)?)?;
+        Ok(inner)
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        _: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let Match {
+            value,
+            identifier,
+            some,
+            none,
+        } = self;
+        let scratch_id = context.scratch_id;
+        let some_context = CompilationContext {
+            scope: context.scope.add(
+                identifier.to_string(),
+                CompilationBinding::ScratchVar(scratch_id),
+            ),
+            scratch_id: scratch_id
+                .checked_add(1)
+                .ok_or(CompilationError::OutOfScratchSpace)?,
+            ..*context
+        };
+        let none_label_id = format!("none{}", create_label_id());
+        let endmatch_label_id = format!("endmatch{}", create_label_id());
+        Ok([
+            value.compile(context, &mut vec![])?,
+            format!("bz {none_label_id}"),
+            format!("store {scratch_id}"),
+            some.compile(&some_context, &mut vec![])?,
+            format!("b {endmatch_label_id}"),
+            format!("{none_label_id}:"),
+            "pop".to_string(),
+            none.compile(context, &mut vec![])?,
+            format!("{endmatch_label_id}:"),
+        ]
+        .join(OP_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply, bind_let, binop, bytes,
+        context::TypeContext,
+        expression::{
+            app_state::AppStateEx,
+            apply::Apply,
+            binary::Binary,
+            bind::Bind,
+            operator::Operator,
+            option::{Match, OptionOp},
+            primitive::Primitive,
+            var::{RVal, Var},
+            Expr, Expression,
+        },
+        int,
+    };
+
+    fn get_ex() -> Expr {
+        apply!(
+            @fn Expr::AppStateEx(AppStateEx::Global);
+            @arg int!(1);
+            @arg bytes!("counter".into());
+        )
+    }
+
+    #[test]
+    fn unwrap_or() {
+        let e = binop!(
+            (apply!(@fn Expr::OptionOp(OptionOp::UnwrapOr); @arg get_ex(); @arg int!(0)))
+                + (int!(1))
+        );
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        let compiled = e.compile_raw().unwrap();
+        println!("{compiled}");
+        assert!(compiled.starts_with("int 1\nbyte \"counter\"\napp_global_get_ex\nbnz unwrap"));
+    }
+
+    #[test]
+    fn is_some() {
+        let e = apply!(@fn Expr::OptionOp(OptionOp::IsSome); @arg get_ex());
+        e.resolve(&TypeContext::default()).unwrap();
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "int 1\nbyte \"counter\"\napp_global_get_ex\nswap\npop"
+        );
+    }
+
+    #[test]
+    fn match_binds_value() {
+        let e = Expr::Match(Box::new(Match {
+            value: get_ex(),
+            identifier: "counter".to_string(),
+            some: binop!((Expr::RVal(RVal(Var::Bind("counter".to_string())))) * (int!(2))),
+            none: int!(0),
+        }));
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        println!("{}", e.compile_raw().unwrap());
+
+        let e = Expr::Match(Box::new(Match {
+            value: get_ex(),
+            identifier: "counter".to_string(),
+            some: Expr::RVal(RVal(Var::Bind("counter".to_string()))),
+            none: bytes!("".into()),
+        }));
+        assert!(e.resolve(&TypeContext::default()).is_ok());
+    }
+
+    #[test]
+    fn unhandled() {
+        let e = bind_let!(x = get_ex(); int!(1));
+        assert!(e.resolve(&TypeContext::default()).is_err());
+    }
+}
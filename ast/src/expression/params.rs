@@ -0,0 +1,292 @@
+use strum_macros::EnumString;
+
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, TypeContext},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{pop_args, Expression, Prepared};
+
+/// Balance queries, typed `account -> int`. Accounts may be given as an
+/// address or an index into `Txn.Accounts`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Balance {
+    Balance,
+    MinBalance,
+}
+
+impl Balance {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "balance" => Balance::Balance,
+            "min_balance" => Balance::MinBalance,
+            _ => return None,
+        })
+    }
+}
+
+impl Expression for Balance {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        Ok(typesig!(:account -> int))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let (opcode, version) = match self {
+            Balance::Balance => ("balance", 2),
+            Balance::MinBalance => ("min_balance", 3),
+        };
+        context.require_version(opcode, version)?;
+        let mut pieces = pop_args(prepared_stack, 1)?;
+        pieces.push(opcode.to_string());
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+/// Fields of `asset_holding_get`, e.g. `AssetHolding[account][asset].AssetBalance`.
+/// Typed `account -> int -> option<T>`.
+#[derive(Debug, Clone, PartialEq, EnumString)]
+pub enum AssetHolding {
+    AssetBalance,
+    AssetFrozen,
+}
+
+impl Expression for AssetHolding {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let value = TypeEnum::Option(Box::new(typesig!(int)));
+        Ok(typesig!(:account -> int -> #value))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        context.require_version("asset_holding_get", 2)?;
+        let mut pieces = pop_args(prepared_stack, 2)?;
+        pieces.push(format!("asset_holding_get {self:?}"));
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+/// Fields of `asset_params_get`, e.g. `AssetParams[asset].AssetDecimals`.
+/// Typed `int -> option<T>`.
+#[derive(Debug, Clone, PartialEq, EnumString)]
+pub enum AssetParams {
+    AssetTotal,
+    AssetDecimals,
+    AssetDefaultFrozen,
+    AssetUnitName,
+    AssetName,
+    AssetURL,
+    AssetMetadataHash,
+    AssetManager,
+    AssetReserve,
+    AssetFreeze,
+    AssetClawback,
+    AssetCreator,
+}
+
+impl AssetParams {
+    pub fn field_type(&self) -> TypeEnum {
+        match self {
+            AssetParams::AssetTotal
+            | AssetParams::AssetDecimals
+            | AssetParams::AssetDefaultFrozen => typesig!(int),
+            _ => typesig!(bytes),
+        }
+    }
+
+    /// Minimum TEAL version in which this field is available.
+    pub fn version(&self) -> u64 {
+        match self {
+            AssetParams::AssetCreator => 5,
+            _ => 2,
+        }
+    }
+}
+
+impl Expression for AssetParams {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let value = TypeEnum::Option(Box::new(self.field_type()));
+        Ok(typesig!(int -> #value))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        context.require_version(&format!("Asset parameter {self:?}"), self.version())?;
+        let mut pieces = pop_args(prepared_stack, 1)?;
+        pieces.push(format!("asset_params_get {self:?}"));
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+/// Fields of `app_params_get`, e.g. `AppParams[application].AppCreator`.
+/// Typed `int -> option<T>`.
+#[derive(Debug, Clone, PartialEq, EnumString)]
+pub enum AppParams {
+    AppApprovalProgram,
+    AppClearStateProgram,
+    AppGlobalNumUint,
+    AppGlobalNumByteSlice,
+    AppLocalNumUint,
+    AppLocalNumByteSlice,
+    AppExtraProgramPages,
+    AppCreator,
+    AppAddress,
+}
+
+impl AppParams {
+    pub fn field_type(&self) -> TypeEnum {
+        match self {
+            AppParams::AppApprovalProgram
+            | AppParams::AppClearStateProgram
+            | AppParams::AppCreator
+            | AppParams::AppAddress => typesig!(bytes),
+            _ => typesig!(int),
+        }
+    }
+}
+
+impl Expression for AppParams {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let value = TypeEnum::Option(Box::new(self.field_type()));
+        Ok(typesig!(int -> #value))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        context.require_version("app_params_get", 5)?;
+        let mut pieces = pop_args(prepared_stack, 1)?;
+        pieces.push(format!("app_params_get {self:?}"));
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+/// Fields of `acct_params_get`, e.g. `AcctParams[account].AcctAuthAddr`.
+/// Typed `account -> option<T>`.
+#[derive(Debug, Clone, PartialEq, EnumString)]
+pub enum AcctParams {
+    AcctBalance,
+    AcctMinBalance,
+    AcctAuthAddr,
+    AcctTotalNumUint,
+    AcctTotalNumByteSlice,
+    AcctTotalExtraAppPages,
+    AcctTotalAppsCreated,
+    AcctTotalAppsOptedIn,
+    AcctTotalAssetsCreated,
+    AcctTotalAssets,
+    AcctTotalBoxes,
+    AcctTotalBoxBytes,
+}
+
+impl AcctParams {
+    pub fn field_type(&self) -> TypeEnum {
+        match self {
+            AcctParams::AcctAuthAddr => typesig!(bytes),
+            _ => typesig!(int),
+        }
+    }
+
+    /// Minimum TEAL version in which this field is available.
+    pub fn version(&self) -> u64 {
+        match self {
+            AcctParams::AcctBalance | AcctParams::AcctMinBalance | AcctParams::AcctAuthAddr => 6,
+            _ => 8,
+        }
+    }
+}
+
+impl Expression for AcctParams {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let value = TypeEnum::Option(Box::new(self.field_type()));
+        Ok(typesig!(:account -> #value))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        context.require_version(&format!("Account parameter {self:?}"), self.version())?;
+        let mut pieces = pop_args(prepared_stack, 1)?;
+        pieces.push(format!("acct_params_get {self:?}"));
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply,
+        context::{CompilationContext, TypeContext},
+        expression::{
+            apply::Apply,
+            option::OptionOp,
+            params::{AcctParams, AssetHolding, AssetParams},
+            primitive::Primitive,
+            txn::Txn,
+            Expr, Expression,
+        },
+        int,
+    };
+
+    #[test]
+    fn typed_per_field() {
+        let decimals = apply!(
+            @fn Expr::OptionOp(OptionOp::UnwrapOr);
+            @arg apply!(@fn Expr::AssetParams(AssetParams::AssetDecimals); @arg int!(0));
+            @arg int!(0);
+        );
+        assert_eq!(
+            decimals
+                .resolve(&TypeContext::default())
+                .unwrap()
+                .resolved()
+                .to_string(),
+            "int"
+        );
+
+        let manager = apply!(
+            @fn Expr::OptionOp(OptionOp::UnwrapOr);
+            @arg apply!(@fn Expr::AssetParams(AssetParams::AssetManager); @arg int!(0));
+            @arg int!(0);
+        );
+        assert!(manager.resolve(&TypeContext::default()).is_err());
+    }
+
+    #[test]
+    fn compile() {
+        let e = apply!(
+            @fn Expr::AssetHolding(AssetHolding::AssetBalance);
+            @arg Expr::Txn(Txn::Sender);
+            @arg Expr::Txn(Txn::XferAsset);
+        );
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "txn Sender\ntxn XferAsset\nasset_holding_get AssetBalance"
+        );
+
+        let e =
+            apply!(@fn Expr::AcctParams(AcctParams::AcctTotalBoxes); @arg Expr::Txn(Txn::Sender));
+        let v6 = CompilationContext {
+            version: 6,
+            ..Default::default()
+        };
+        assert!(e.compile(&v6, &mut vec![]).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::ascii;
+use base64::{engine::general_purpose::STANDARD, Engine};
 
 use crate::{
     compilation_error::CompilationError,
@@ -7,7 +7,7 @@ use crate::{
     typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
 };
 
-use super::Expression;
+use super::{Expression, Prepared};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Primitive {
@@ -40,6 +40,32 @@ impl From<u64> for Primitive {
     }
 }
 
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Escapes `value` as a TEAL string literal, keeping printable ASCII as is.
+fn escape(value: &[u8]) -> String {
+    value
+        .iter()
+        .map(|c| match c {
+            b'"' => "\\\"".to_string(),
+            b'\\' => "\\\\".to_string(),
+            b'\n' => "\\n".to_string(),
+            b'\r' => "\\r".to_string(),
+            b'\t' => "\\t".to_string(),
+            b' '..=b'~' => (*c as char).to_string(),
+            _ => format!("\\x{c:02x}"),
+        })
+        .collect()
+}
+
 impl Expression for Primitive {
     fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
         Ok(match self {
@@ -51,20 +77,49 @@ impl Expression for Primitive {
     fn compile(
         &self,
         _: &CompilationContext,
-        _: &mut Vec<String>,
+        _: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
         match self {
             Self::UInt64(value) => Ok(format!("int {value}")),
-            Self::Byteslice(value) => {
-                let escaped = String::from_utf8(
-                    value
-                        .into_iter()
-                        .flat_map(|c| ascii::escape_default(*c))
-                        .collect::<Vec<u8>>(),
-                )?;
+            // the shortest of a string, hex and base64 literal, preferring them
+            // in that order
+            Self::Byteslice(value) => Ok([
+                format!("byte \"{}\"", escape(value)),
+                format!(
+                    "byte 0x{}",
+                    value.iter().map(|c| format!("{c:02x}")).collect::<String>()
+                ),
+                format!("byte base64 {}", STANDARD.encode(value)),
+            ]
+            .into_iter()
+            .min_by_key(String::len)
+            .unwrap()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+
+    use super::Primitive;
 
-                Ok(format!("byte \"{escaped}\""))
-            }
+    #[test]
+    fn bytes_literals() {
+        for (value, expected) in [
+            (&b"key"[..], "byte \"key\""),
+            (b"", "byte \"\""),
+            (b"a\nb", "byte \"a\\nb\""),
+            (b"say \"hi\"", "byte \"say \\\"hi\\\"\""),
+            (b"\x15\x1f|u", "byte 0x151f7c75"),
+            (
+                &[0xff; 32],
+                "byte base64 //////////////////////////////////////////8=",
+            ),
+        ] {
+            let compiled = Primitive::Byteslice(value.to_vec()).compile_raw().unwrap();
+            println!("{compiled}");
+            assert_eq!(compiled, expected);
         }
     }
 }
@@ -6,7 +6,7 @@ use crate::{
     OP_SEPARATOR,
 };
 
-use super::Expression;
+use super::{pop_arg, Expression, Prepared};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Ret;
@@ -19,11 +19,11 @@ impl Expression for Ret {
     fn compile(
         &self,
         _context: &CompilationContext,
-        prepared_stack: &mut Vec<String>,
+        prepared_stack: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
         Ok(format!(
             "{}{OP_SEPARATOR}return",
-            prepared_stack.pop().ok_or(CompilationError::MissingStack)?
+            pop_arg(prepared_stack)?.compiled
         ))
     }
 }
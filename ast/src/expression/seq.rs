@@ -5,7 +5,7 @@ use crate::{
     OP_SEPARATOR,
 };
 
-use super::{Expr, Expression};
+use super::{Expr, Expression, Prepared};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Seq(pub Expr, pub Option<Expr>);
@@ -15,14 +15,15 @@ impl Expression for Seq {
         match (self.0.resolve(context)?, &self.1) {
             (h @ TypeEnum::Simple(TypePrimitive::Halt), _) | (h, None) => Ok(h),
             (TypeEnum::Simple(TypePrimitive::Void), Some(t)) => t.resolve(context),
-            _ => todo!(),
+            // values left on the stack would be mistaken for the result
+            (head, Some(_)) => Err(TypeError::DiscardedValue(head)),
         }
     }
 
     fn compile(
         &self,
         context: &CompilationContext,
-        _: &mut Vec<String>,
+        _: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
         let Self(head, tail) = self;
 
@@ -5,50 +5,484 @@ use crate::{
     context::{CompilationContext, TypeContext},
     typesig,
     typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
 };
 
-use super::Expression;
+use super::{pop_arg, Expression, Prepared};
 
-// TODO: Incomplete
 #[derive(Debug, Clone, PartialEq, EnumString)]
 pub enum Txn {
     Sender,
     Fee,
+    FirstValid,
+    FirstValidTime,
+    LastValid,
+    Note,
+    Lease,
     Receiver,
     Amount,
     CloseRemainderTo,
+    VotePK,
+    SelectionPK,
+    VoteFirst,
+    VoteLast,
+    VoteKeyDilution,
+    Type,
+    TypeEnum,
+    XferAsset,
+    AssetAmount,
+    AssetSender,
+    AssetReceiver,
+    AssetCloseTo,
     GroupIndex,
+    TxID,
     ApplicationID,
     OnCompletion,
+    ApplicationArgs,
+    NumAppArgs,
     Accounts,
     NumAccounts,
+    ApprovalProgram,
+    ClearStateProgram,
+    RekeyTo,
+    ConfigAsset,
+    ConfigAssetTotal,
+    ConfigAssetDecimals,
+    ConfigAssetDefaultFrozen,
+    ConfigAssetUnitName,
+    ConfigAssetName,
+    ConfigAssetURL,
+    ConfigAssetMetadataHash,
+    ConfigAssetManager,
+    ConfigAssetReserve,
+    ConfigAssetFreeze,
+    ConfigAssetClawback,
+    FreezeAsset,
+    FreezeAssetAccount,
+    FreezeAssetFrozen,
+    Assets,
+    NumAssets,
+    Applications,
+    NumApplications,
+    GlobalNumUint,
+    GlobalNumByteSlice,
+    LocalNumUint,
+    LocalNumByteSlice,
+    ExtraProgramPages,
+    Nonparticipation,
+    Logs,
+    NumLogs,
+    CreatedAssetID,
+    CreatedApplicationID,
+    LastLog,
+    StateProofPK,
+    ApprovalProgramPages,
+    NumApprovalProgramPages,
+    ClearStateProgramPages,
+    NumClearStateProgramPages,
+}
+
+impl Txn {
+    /// Type of a single value of this field (an element, for array fields).
+    pub fn field_type(&self) -> TypeEnum {
+        match self {
+            Txn::Sender
+            | Txn::Note
+            | Txn::Lease
+            | Txn::Receiver
+            | Txn::CloseRemainderTo
+            | Txn::VotePK
+            | Txn::SelectionPK
+            | Txn::Type
+            | Txn::AssetSender
+            | Txn::AssetReceiver
+            | Txn::AssetCloseTo
+            | Txn::TxID
+            | Txn::ApplicationArgs
+            | Txn::Accounts
+            | Txn::ApprovalProgram
+            | Txn::ClearStateProgram
+            | Txn::RekeyTo
+            | Txn::ConfigAssetUnitName
+            | Txn::ConfigAssetName
+            | Txn::ConfigAssetURL
+            | Txn::ConfigAssetMetadataHash
+            | Txn::ConfigAssetManager
+            | Txn::ConfigAssetReserve
+            | Txn::ConfigAssetFreeze
+            | Txn::ConfigAssetClawback
+            | Txn::FreezeAssetAccount
+            | Txn::Logs
+            | Txn::LastLog
+            | Txn::StateProofPK
+            | Txn::ApprovalProgramPages
+            | Txn::ClearStateProgramPages => typesig!(bytes),
+            _ => typesig!(int),
+        }
+    }
+
+    /// Array fields must be accessed with an index (`txna`, `gtxna`, ...).
+    pub fn is_array(&self) -> bool {
+        matches!(
+            self,
+            Txn::ApplicationArgs
+                | Txn::Accounts
+                | Txn::Assets
+                | Txn::Applications
+                | Txn::Logs
+                | Txn::ApprovalProgramPages
+                | Txn::ClearStateProgramPages
+        )
+    }
+
+    /// Minimum TEAL version in which this field is available.
+    pub fn version(&self) -> u64 {
+        match self {
+            Txn::FirstValidTime
+            | Txn::ApprovalProgramPages
+            | Txn::NumApprovalProgramPages
+            | Txn::ClearStateProgramPages
+            | Txn::NumClearStateProgramPages => 7,
+            Txn::LastLog | Txn::StateProofPK => 6,
+            Txn::Nonparticipation
+            | Txn::Logs
+            | Txn::NumLogs
+            | Txn::CreatedAssetID
+            | Txn::CreatedApplicationID => 5,
+            Txn::ExtraProgramPages => 4,
+            Txn::Assets
+            | Txn::NumAssets
+            | Txn::Applications
+            | Txn::NumApplications
+            | Txn::GlobalNumUint
+            | Txn::GlobalNumByteSlice
+            | Txn::LocalNumUint
+            | Txn::LocalNumByteSlice => 3,
+            Txn::ApplicationID
+            | Txn::OnCompletion
+            | Txn::ApplicationArgs
+            | Txn::NumAppArgs
+            | Txn::Accounts
+            | Txn::NumAccounts
+            | Txn::ApprovalProgram
+            | Txn::ClearStateProgram
+            | Txn::RekeyTo
+            | Txn::ConfigAsset
+            | Txn::ConfigAssetTotal
+            | Txn::ConfigAssetDecimals
+            | Txn::ConfigAssetDefaultFrozen
+            | Txn::ConfigAssetUnitName
+            | Txn::ConfigAssetName
+            | Txn::ConfigAssetURL
+            | Txn::ConfigAssetMetadataHash
+            | Txn::ConfigAssetManager
+            | Txn::ConfigAssetReserve
+            | Txn::ConfigAssetFreeze
+            | Txn::ConfigAssetClawback
+            | Txn::FreezeAsset
+            | Txn::FreezeAssetAccount
+            | Txn::FreezeAssetFrozen => 2,
+            _ => 1,
+        }
+    }
+
+    fn check(&self, context: &CompilationContext, indexed: bool) -> Result<(), CompilationError> {
+        self.check_type(indexed)?;
+        context.require_version(&format!("Transaction field {self:?}"), self.version())
+    }
+
+    fn check_type(&self, indexed: bool) -> Result<TypeEnum, TypeError> {
+        match (self.is_array(), indexed) {
+            (true, false) => Err(TypeError::MissingArrayIndex(self.clone())),
+            (false, true) => Err(TypeError::NotAnArrayField(self.clone())),
+            _ => Ok(self.field_type()),
+        }
+    }
 }
 
 impl Expression for Txn {
     fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
-        Ok(match self {
-            Txn::Sender | Txn::Receiver | Txn::CloseRemainderTo | Txn::Accounts => {
-                typesig!(bytes)
+        self.check_type(false)
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        _: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        self.check(context, false)?;
+        Ok(format!("txn {:?}", self))
+    }
+}
+
+/// Indexed access to an array field of the current transaction, e.g.
+/// `Txn.Accounts[1]`. Typed `int -> T`, where the argument is the array index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Txna(pub Txn);
+
+impl Expression for Txna {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let field_type = self.0.check_type(true)?;
+        Ok(typesig!(int -> #field_type))
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let Txna(field) = self;
+        field.check(context, true)?;
+        let index = pop_arg(prepared_stack)?;
+        match index.immediate() {
+            Some(i) => Ok(format!("txna {field:?} {i}")),
+            None => {
+                context.require_version("txnas", 5)?;
+                Ok(format!("{}{OP_SEPARATOR}txnas {field:?}", index.compiled))
             }
-            _ => typesig!(int),
+        }
+    }
+}
+
+/// Access to a field of another transaction in the atomic group, e.g.
+/// `Gtxn[0].Amount` or `Gtxn[1].ApplicationArgs[0]`.
+///
+/// Typed `int -> T`, where the argument is the group index, or
+/// `int -> int -> T` for array fields, where the second argument is the array
+/// index. Constant indices compile to the immediate forms (`gtxn`, `gtxna`),
+/// while computed indices use the stack forms (`gtxns`, `gtxnsa`, `gtxnas`,
+/// `gtxnsas`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gtxn {
+    pub field: Txn,
+    pub array: bool,
+}
+
+impl Expression for Gtxn {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let field_type = self.field.check_type(self.array)?;
+        Ok(if self.array {
+            typesig!(int -> int -> #field_type)
+        } else {
+            typesig!(int -> #field_type)
         })
     }
 
     fn compile(
         &self,
-        _: &CompilationContext,
-        _: &mut Vec<String>,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
-        Ok(format!("txn {:?}", self))
+        let Gtxn { field, array } = self;
+        field.check(context, *array)?;
+        let group_index = pop_arg(prepared_stack)?;
+        let array_index = if *array {
+            Some(pop_arg(prepared_stack)?)
+        } else {
+            None
+        };
+
+        let (mut pieces, group_immediate) = match group_index.immediate() {
+            Some(g) => (vec![], Some(g)),
+            None => {
+                context.require_version("gtxns", 3)?;
+                (vec![group_index.compiled], None)
+            }
+        };
+
+        pieces.push(match (group_immediate, array_index) {
+            (Some(g), None) => format!("gtxn {g} {field:?}"),
+            (None, None) => format!("gtxns {field:?}"),
+            (group_immediate, Some(array_index)) => {
+                match (group_immediate, array_index.immediate()) {
+                    (Some(g), Some(i)) => format!("gtxna {g} {field:?} {i}"),
+                    (None, Some(i)) => format!("gtxnsa {field:?} {i}"),
+                    (Some(g), None) => {
+                        context.require_version("gtxnas", 5)?;
+                        format!("{}{OP_SEPARATOR}gtxnas {g} {field:?}", array_index.compiled)
+                    }
+                    (None, None) => {
+                        context.require_version("gtxnsas", 5)?;
+                        format!("{}{OP_SEPARATOR}gtxnsas {field:?}", array_index.compiled)
+                    }
+                }
+            }
+        });
+
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+/// Access to a field of the last submitted inner transaction, e.g.
+/// `Itxn.CreatedAssetID` or `Itxn.Logs[0]`. Typed `T`, or `int -> T` for
+/// array fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Itxn {
+    pub field: Txn,
+    pub array: bool,
+}
+
+impl Expression for Itxn {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let field_type = self.field.check_type(self.array)?;
+        Ok(if self.array {
+            typesig!(int -> #field_type)
+        } else {
+            field_type
+        })
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let Itxn { field, array } = self;
+        field.check(context, *array)?;
+        context.require_version("itxn", 5)?;
+        if !array {
+            return Ok(format!("itxn {field:?}"));
+        }
+        let index = pop_arg(prepared_stack)?;
+        match index.immediate() {
+            Some(i) => Ok(format!("itxna {field:?} {i}")),
+            None => {
+                context.require_version("itxnas", 6)?;
+                Ok(format!("{}{OP_SEPARATOR}itxnas {field:?}", index.compiled))
+            }
+        }
+    }
+}
+
+/// Access to a field of a transaction in the last submitted inner group,
+/// e.g. `Gitxn[0].Amount`. Typed like [`Gtxn`], but the group index must be
+/// a constant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gitxn {
+    pub field: Txn,
+    pub array: bool,
+}
+
+impl Expression for Gitxn {
+    fn resolve(&self, _: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let field_type = self.field.check_type(self.array)?;
+        Ok(if self.array {
+            typesig!(int -> int -> #field_type)
+        } else {
+            typesig!(int -> #field_type)
+        })
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        let Gitxn { field, array } = self;
+        field.check(context, *array)?;
+        context.require_version("gitxn", 6)?;
+        let g = pop_arg(prepared_stack)?
+            .immediate()
+            .ok_or_else(|| CompilationError::NonConstantGroupIndex("gitxn".to_string()))?;
+        if !array {
+            return Ok(format!("gitxn {g} {field:?}"));
+        }
+        let index = pop_arg(prepared_stack)?;
+        Ok(match index.immediate() {
+            Some(i) => format!("gitxna {g} {field:?} {i}"),
+            None => format!("{}{OP_SEPARATOR}gitxnas {g} {field:?}", index.compiled),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::expression::txn::Txn;
+    use crate::{
+        apply, binop,
+        context::{CompilationContext, TypeContext},
+        expression::{
+            apply::Apply,
+            binary::Binary,
+            operator::Operator,
+            primitive::Primitive,
+            txn::{Gitxn, Gtxn, Itxn, Txn, Txna},
+            Expr, Expression,
+        },
+        int,
+    };
 
     #[test]
     fn test() {
         println!("{:?}", Txn::Sender);
     }
+
+    #[test]
+    fn array_fields() {
+        assert!(Txn::Accounts.resolve(&TypeContext::default()).is_err());
+        assert!(Txna(Txn::Amount).resolve(&TypeContext::default()).is_err());
+        let e = apply!(@fn Expr::Txna(Txna(Txn::ApplicationArgs)); @arg int!(1));
+        assert_eq!(e.compile_raw().unwrap(), "txna ApplicationArgs 1");
+    }
+
+    #[test]
+    fn gtxn_constant_index() {
+        let e = apply!(
+            @fn Expr::Gtxn(Gtxn { field: Txn::Amount, array: false });
+            @arg int!(0);
+        );
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        assert_eq!(e.compile_raw().unwrap(), "gtxn 0 Amount");
+
+        let e = apply!(
+            @fn Expr::Gtxn(Gtxn { field: Txn::Accounts, array: true });
+            @arg int!(1);
+            @arg int!(2);
+        );
+        assert_eq!(e.compile_raw().unwrap(), "gtxna 1 Accounts 2");
+    }
+
+    #[test]
+    fn gtxn_computed_index() {
+        let e = apply!(
+            @fn Expr::Gtxn(Gtxn { field: Txn::Amount, array: false });
+            @arg binop!((Expr::Txn(Txn::GroupIndex)) - (int!(1)));
+        );
+        e.resolve(&TypeContext::default()).unwrap();
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "txn GroupIndex\nint 1\n-\ngtxns Amount"
+        );
+
+        let old = CompilationContext {
+            version: 2,
+            ..Default::default()
+        };
+        assert!(e.compile(&old, &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn inner_results() {
+        let e = Expr::Itxn(Itxn {
+            field: Txn::CreatedAssetID,
+            array: false,
+        });
+        println!("{}", e.resolve(&TypeContext::default()).unwrap());
+        assert_eq!(e.compile_raw().unwrap(), "itxn CreatedAssetID");
+
+        let e = apply!(@fn Expr::Itxn(Itxn { field: Txn::Logs, array: true }); @arg int!(0));
+        assert_eq!(e.compile_raw().unwrap(), "itxna Logs 0");
+
+        let e = apply!(
+            @fn Expr::Gitxn(Gitxn { field: Txn::Amount, array: false });
+            @arg int!(1);
+        );
+        e.resolve(&TypeContext::default()).unwrap();
+        assert_eq!(e.compile_raw().unwrap(), "gitxn 1 Amount");
+
+        let e = apply!(
+            @fn Expr::Gitxn(Gitxn { field: Txn::Amount, array: false });
+            @arg Expr::Txn(Txn::GroupIndex);
+        );
+        assert!(e.compile_raw().is_err());
+    }
 }
@@ -0,0 +1,100 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, TypeContext},
+    expression::{pop_arg, primitive::Primitive, Expression, Prepared},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{box_map_types, box_name, Var};
+
+/// Removes a state variable, e.g. `delete local[account].x`, which refunds
+/// the minimum balance requirement associated with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delete(pub Var);
+
+impl Expression for Delete {
+    fn resolve(&self, context: &TypeContext) -> Result<TypeEnum, TypeError> {
+        // only declared keys may be deleted
+        let type_enum = self.0.get_type(context)?;
+
+        match self.0 {
+            Var::Global(..) | Var::Box(..) => Ok(typesig!(void)),
+            Var::Local(..) => Ok(typesig!(int -> void)),
+            Var::BoxMap(..) => {
+                let (key, _) = box_map_types(type_enum);
+                Ok(typesig!(#key -> void))
+            }
+            Var::Bind(..) | Var::Template(..) => Err(TypeError::NotStateVariable(self.0.clone())),
+        }
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        match (&self.0, context.global_layout, context.local_layout) {
+            (Var::Global(identifier), Some(_), _) | (Var::Local(identifier), _, Some(_)) => Err(
+                CompilationError::PackedFieldDeletion(identifier.to_string()),
+            ),
+            (Var::Global(identifier), ..) => Ok([
+                Primitive::from(identifier).compile(context, &mut vec![])?,
+                "app_global_del".to_string(),
+            ]
+            .join(OP_SEPARATOR)),
+            (Var::Local(identifier), ..) => Ok([
+                pop_arg(prepared_stack)?.compiled,
+                Primitive::from(identifier).compile(context, &mut vec![])?,
+                "app_local_del".to_string(),
+            ]
+            .join(OP_SEPARATOR)),
+            (var @ (Var::Box(_) | Var::BoxMap(_)), ..) => {
+                let (mut pieces, _) = box_name(context, var, prepared_stack)?;
+                pieces.extend(["box_del".to_string(), "pop".to_string()]);
+                Ok(pieces.join(OP_SEPARATOR))
+            }
+            (Var::Bind(..) | Var::Template(..), ..) => {
+                Err(TypeError::NotStateVariable(self.0.clone()).into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{
+        apply,
+        context::{Scope, TypeContext},
+        expression::{apply::Apply, primitive::Primitive, var::Var, Expr, Expression},
+        int,
+        typing::{TypeEnum, TypePrimitive},
+    };
+
+    use super::Delete;
+
+    #[test]
+    fn test() {
+        let context = TypeContext {
+            local_scope: Rc::new(Scope::from_iter([(
+                "key".to_string(),
+                TypeEnum::Simple(TypePrimitive::UInt64),
+            )])),
+            ..Default::default()
+        };
+        let e = apply!(@fn Expr::Delete(Delete(Var::Local("key".to_string()))); @arg int!(0));
+        println!("{}", e.resolve(&context).unwrap());
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "int 0\nbyte \"key\"\napp_local_del"
+        );
+
+        let e = Delete(Var::Global("key".to_string()));
+        assert!(e.resolve(&context).is_err());
+        let e = Delete(Var::Bind("key".to_string()));
+        assert!(e.resolve(&context).is_err());
+    }
+}
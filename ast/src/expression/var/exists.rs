@@ -0,0 +1,101 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, TypeContext},
+    expression::{pop_arg, primitive::Primitive, Expression, Prepared},
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    OP_SEPARATOR,
+};
+
+use super::{box_map_types, box_name, Var};
+
+/// Checks whether a state variable has been set, e.g. `exists(global.x)`,
+/// as opposed to reading it, which yields 0 for missing keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exists(pub Var);
+
+impl Expression for Exists {
+    fn resolve(&self, context: &TypeContext) -> Result<TypeEnum, TypeError> {
+        let type_enum = self.0.get_type(context)?;
+
+        match self.0 {
+            Var::Global(..) | Var::Box(..) => Ok(typesig!(int)),
+            Var::Local(..) => Ok(typesig!(int -> int)),
+            Var::BoxMap(..) => {
+                let (key, _) = box_map_types(type_enum);
+                Ok(typesig!(#key -> int))
+            }
+            Var::Bind(..) | Var::Template(..) => Err(TypeError::NotStateVariable(self.0.clone())),
+        }
+    }
+
+    fn compile(
+        &self,
+        context: &CompilationContext,
+        prepared_stack: &mut Vec<Prepared>,
+    ) -> Result<String, CompilationError> {
+        // application 0 refers to the current application; fields of packed
+        // schemas exist as soon as any of them has been written
+        let pieces = match &self.0 {
+            Var::Global(identifier) => vec![
+                "int 0".to_string(),
+                Primitive::from(context.global_layout.map_or(identifier, |l| &l.key))
+                    .compile(context, &mut vec![])?,
+                "app_global_get_ex".to_string(),
+            ],
+            Var::Local(identifier) => vec![
+                pop_arg(prepared_stack)?.compiled,
+                "int 0".to_string(),
+                Primitive::from(context.local_layout.map_or(identifier, |l| &l.key))
+                    .compile(context, &mut vec![])?,
+                "app_local_get_ex".to_string(),
+            ],
+            var @ (Var::Box(_) | Var::BoxMap(_)) => {
+                let (mut pieces, _) = box_name(context, var, prepared_stack)?;
+                pieces.push("box_len".to_string());
+                pieces
+            }
+            Var::Bind(..) | Var::Template(..) => {
+                return Err(TypeError::NotStateVariable(self.0.clone()).into())
+            }
+        };
+        Ok(pieces
+            .into_iter()
+            .chain(["swap".to_string(), "pop".to_string()])
+            .collect::<Vec<_>>()
+            .join(OP_SEPARATOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{
+        context::{Scope, TypeContext},
+        expression::{var::Var, Expression},
+        typing::{TypeEnum, TypePrimitive},
+    };
+
+    use super::Exists;
+
+    #[test]
+    fn test() {
+        let context = TypeContext {
+            global_scope: Rc::new(Scope::from_iter([(
+                "key".to_string(),
+                TypeEnum::Simple(TypePrimitive::Byteslice),
+            )])),
+            ..Default::default()
+        };
+        let e = Exists(Var::Global("key".to_string()));
+        println!("{}", e.resolve(&context).unwrap());
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "int 0\nbyte \"key\"\napp_global_get_ex\nswap\npop"
+        );
+        assert!(Exists(Var::Global("missing".to_string()))
+            .resolve(&context)
+            .is_err());
+    }
+}
@@ -1,13 +1,13 @@
 use crate::{
     compilation_error::CompilationError,
     context::{CompilationBinding, CompilationContext, TypeContext},
-    expression::{primitive::Primitive, Expression},
+    expression::{pop_arg, primitive::Primitive, Expression, Prepared},
     typesig,
     typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
     OP_SEPARATOR,
 };
 
-use super::Var;
+use super::{box_map_types, box_name, check_state_entry, load_packed, write_packed, Var};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LVal(pub Var);
@@ -17,7 +17,12 @@ impl Expression for LVal {
         let type_enum = self.0.get_type(context)?.clone();
 
         Ok(match self.0 {
+            Var::Template(..) => return Err(TypeError::NotStateVariable(self.0.clone())),
             Var::Local(..) => typesig!(int -> #type_enum -> void),
+            Var::BoxMap(..) => {
+                let (key, value) = box_map_types(&type_enum);
+                typesig!(#key -> #value -> void)
+            }
             _ => typesig!(#type_enum -> void),
         })
     }
@@ -25,11 +30,40 @@ impl Expression for LVal {
     fn compile(
         &self,
         context: &CompilationContext,
-        prepared_stack: &mut Vec<String>,
+        prepared_stack: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
-        match &self.0 {
-            Var::Bind(identifier) => {
-                let what = prepared_stack.pop().ok_or(CompilationError::MissingStack)?;
+        match (&self.0, context.global_layout, context.local_layout) {
+            (Var::Global(identifier), Some(layout), _) => {
+                let what = pop_arg(prepared_stack)?;
+                let mut pieces = vec![Primitive::from(&layout.key).compile(context, &mut vec![])?];
+                pieces.extend(load_packed(context, layout, None)?);
+                pieces.extend(write_packed(identifier, layout.get(identifier)?, what)?);
+                pieces.push("app_global_put".to_string());
+                Ok(pieces.join(OP_SEPARATOR))
+            }
+            (Var::Local(identifier), _, Some(layout)) => {
+                let who = pop_arg(prepared_stack)?.compiled;
+                let what = pop_arg(prepared_stack)?;
+                let mut pieces = vec![
+                    who.clone(),
+                    Primitive::from(&layout.key).compile(context, &mut vec![])?,
+                ];
+                pieces.extend(load_packed(context, layout, Some(&who))?);
+                pieces.extend(write_packed(identifier, layout.get(identifier)?, what)?);
+                pieces.push("app_local_put".to_string());
+                Ok(pieces.join(OP_SEPARATOR))
+            }
+            (var @ (Var::Box(_) | Var::BoxMap(_)), ..) => {
+                let (mut pieces, primitive) = box_name(context, var, prepared_stack)?;
+                pieces.push(pop_arg(prepared_stack)?.compiled);
+                if primitive == TypePrimitive::UInt64 {
+                    pieces.push("itob".to_string());
+                }
+                pieces.push("box_put".to_string());
+                Ok(pieces.join(OP_SEPARATOR))
+            }
+            (Var::Bind(identifier), ..) => {
+                let what = pop_arg(prepared_stack)?.compiled;
                 let scratch_binding =
                     context.scope.get(identifier).ok_or(CompilationError::from(
                         // should never happen if type checking is run before compilation
@@ -44,22 +78,25 @@ impl Expression for LVal {
                 };
                 Ok(format!("{what}{OP_SEPARATOR}store {scratch_id}"))
             }
-            Var::Global(identifier) => {
-                let what = prepared_stack.pop().ok_or(CompilationError::MissingStack)?;
-                Ok(vec![
+            (Var::Template(..), ..) => Err(TypeError::NotStateVariable(self.0.clone()).into()),
+            (Var::Global(identifier), ..) => {
+                let what = pop_arg(prepared_stack)?;
+                check_state_entry(identifier, &what)?;
+                Ok([
                     Primitive::from(identifier).compile(context, prepared_stack)?,
-                    what,
+                    what.compiled,
                     "app_global_put".to_string(),
                 ]
                 .join(OP_SEPARATOR))
             }
-            Var::Local(identifier) => {
-                let who = prepared_stack.pop().ok_or(CompilationError::MissingStack)?;
-                let what = prepared_stack.pop().ok_or(CompilationError::MissingStack)?;
-                Ok(vec![
+            (Var::Local(identifier), ..) => {
+                let who = pop_arg(prepared_stack)?.compiled;
+                let what = pop_arg(prepared_stack)?;
+                check_state_entry(identifier, &what)?;
+                Ok([
                     who,
                     Primitive::from(identifier).compile(context, prepared_stack)?,
-                    what,
+                    what.compiled,
                     "app_local_put".to_string(),
                 ]
                 .join(OP_SEPARATOR))
@@ -104,6 +141,7 @@ mod tests {
                     scope: Scope::default()
                         .add("key".to_string(), CompilationBinding::ScratchVar(0)),
                     scratch_id: 1,
+                    ..Default::default()
                 },
                 &mut vec![]
             )
@@ -1,11 +1,21 @@
 use crate::{
-    context::TypeContext,
-    typing::{TypeEnum, TypeError},
+    compilation_error::CompilationError,
+    context::{CompilationContext, Mode, TypeContext},
+    label::create_label_id,
+    struct_def::{PackedField, PackedLayout, MAX_KEY_LENGTH, MAX_KEY_VALUE_LENGTH},
+    typing::{TypeEnum, TypeError, TypePrimitive},
+    with_error_message,
 };
 
+use super::{pop_arg, primitive::Primitive, Expression, Prepared};
+
+mod delete;
+mod exists;
 mod lval;
 mod rval;
 
+pub use delete::Delete;
+pub use exists::Exists;
 pub use lval::LVal;
 pub use rval::RVal;
 
@@ -14,6 +24,14 @@ pub enum Var {
     Bind(String),
     Global(String),
     Local(String),
+    /// A box declared in `schema box`
+    Box(String),
+    /// A map of boxes declared in `schema box`, e.g. `balances: map<bytes, uint64>`,
+    /// with each entry stored in a box named by the map's name followed by the
+    /// encoded key
+    BoxMap(String),
+    /// A template variable declared with `template`, fixed at deploy time
+    Template(String),
 }
 
 impl Var {
@@ -22,10 +40,146 @@ impl Var {
             Var::Bind(i) => (i, &context.bind_scope),
             Var::Global(i) => (i, &context.global_scope),
             Var::Local(i) => (i, &context.local_scope),
+            Var::Box(i) | Var::BoxMap(i) => (i, &context.box_scope),
+            Var::Template(i) => (i, &context.template_scope),
         };
 
         scope
-            .get(&identifier)
+            .get(identifier)
             .ok_or(TypeError::UnboundIdentifier(self.clone()))
     }
 }
+
+/// Pushes the name of the box holding `var`, consuming the key from
+/// `prepared_stack` for box maps, and returns the declared value type.
+pub(crate) fn box_name(
+    context: &CompilationContext,
+    var: &Var,
+    prepared_stack: &mut Vec<Prepared>,
+) -> Result<(Vec<String>, TypePrimitive), CompilationError> {
+    context.require_mode("Box storage", Mode::Application)?;
+    context.require_version("Box storage", 8)?;
+    let unbound = || CompilationError::from(TypeError::UnboundIdentifier(var.clone()));
+    let schema = context.box_schema.ok_or_else(unbound)?;
+    match var {
+        Var::Box(identifier) => {
+            let primitive = schema
+                .fields
+                .get(identifier.as_str())
+                .ok_or_else(unbound)?
+                .clone();
+            Ok((
+                vec![Primitive::from(identifier).compile(context, &mut vec![])?],
+                primitive,
+            ))
+        }
+        Var::BoxMap(identifier) => {
+            let (key_primitive, value_primitive) = schema
+                .maps
+                .get(identifier.as_str())
+                .ok_or_else(unbound)?
+                .clone();
+            let mut pieces = vec![
+                Primitive::from(identifier).compile(context, &mut vec![])?,
+                pop_arg(prepared_stack)?.compiled,
+            ];
+            if key_primitive == TypePrimitive::UInt64 {
+                pieces.push("itob".to_string());
+            }
+            pieces.push("concat".to_string());
+            Ok((pieces, value_primitive))
+        }
+        _ => Err(TypeError::NotStateVariable(var.clone()).into()),
+    }
+}
+
+/// Splits the type of a box map into its key and value types.
+pub(crate) fn box_map_types(type_enum: &TypeEnum) -> (TypeEnum, TypeEnum) {
+    match type_enum {
+        TypeEnum::Arrow(key, value) => (*key.clone(), *value.clone()),
+        _ => unreachable!(),
+    }
+}
+
+/// Checks the size limits of a state entry, as far as they can be known at
+/// compile time, i.e. when the value written is a constant.
+pub(crate) fn check_state_entry(key: &str, value: &Prepared) -> Result<(), CompilationError> {
+    if key.len() > MAX_KEY_LENGTH {
+        return Err(CompilationError::KeyTooLong(key.to_string()));
+    }
+    match value.bytes() {
+        Some(bytes) if key.len() + bytes.len() > MAX_KEY_VALUE_LENGTH => Err(
+            CompilationError::StateEntryTooLarge(key.to_string(), bytes.len()),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Pushes the value of a packed schema key, zero-filled if it has not been
+/// written yet. `account` is only given for local state.
+pub(crate) fn load_packed(
+    context: &CompilationContext,
+    layout: &PackedLayout,
+    account: Option<&str>,
+) -> Result<Vec<String>, CompilationError> {
+    context.require_version("Packed storage", 7)?;
+    let label_id = format!("packed{}", create_label_id());
+    let mut pieces = account.map(str::to_string).into_iter().collect::<Vec<_>>();
+    pieces.extend([
+        // application 0 refers to the current application
+        "int 0".to_string(),
+        Primitive::from(&layout.key).compile(context, &mut vec![])?,
+        if account.is_some() {
+            "app_local_get_ex"
+        } else {
+            "app_global_get_ex"
+        }
+        .to_string(),
+        format!("bnz {label_id}"),
+        "pop".to_string(),
+        format!("int {}", layout.size),
+        "bzero".to_string(),
+        format!("{label_id}:"),
+    ]);
+    Ok(pieces)
+}
+
+/// Reads a field out of the packed value on top of the stack.
+pub(crate) fn read_packed(field: &PackedField) -> Vec<String> {
+    let mut pieces = vec![format!("extract {} {}", field.offset, field.width)];
+    if field.primitive == TypePrimitive::UInt64 {
+        pieces.push("btoi".to_string());
+    }
+    pieces
+}
+
+/// Writes `value` into the packed value on top of the stack. The width of
+/// constant values is checked here, that of other values when the program runs.
+pub(crate) fn write_packed(
+    identifier: &str,
+    field: &PackedField,
+    value: Prepared,
+) -> Result<Vec<String>, CompilationError> {
+    let mut pieces = vec![value.compiled.clone()];
+    match field.primitive {
+        TypePrimitive::UInt64 => pieces.push("itob".to_string()),
+        _ => match value.bytes() {
+            Some(bytes) if bytes.len() != field.width => {
+                return Err(CompilationError::PackedWidthMismatch(
+                    identifier.to_string(),
+                    field.width,
+                ))
+            }
+            Some(_) => {}
+            None => pieces.extend([
+                "dup".to_string(),
+                "len".to_string(),
+                format!("int {}", field.width),
+                "==".to_string(),
+                with_error_message("assert", &format!("{identifier} has wrong length")),
+            ]),
+        },
+    }
+    pieces.push(format!("replace2 {}", field.offset));
+    Ok(pieces)
+}
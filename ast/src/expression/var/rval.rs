@@ -1,13 +1,14 @@
 use crate::{
     compilation_error::CompilationError,
     context::{CompilationBinding, CompilationContext, TypeContext},
-    expression::{primitive::Primitive, Expression},
+    expression::{pop_arg, primitive::Primitive, Expression, Prepared},
+    template::load_template,
     typesig,
     typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
-    OP_SEPARATOR,
+    with_error_message, OP_SEPARATOR,
 };
 
-use super::Var;
+use super::{box_name, load_packed, read_packed, Var};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RVal(pub Var);
@@ -25,22 +26,46 @@ impl Expression for RVal {
     fn compile(
         &self,
         context: &CompilationContext,
-        prepared_stack: &mut Vec<String>,
+        prepared_stack: &mut Vec<Prepared>,
     ) -> Result<String, CompilationError> {
-        match &self.0 {
-            Var::Global(identifier) => Ok(format!(
+        match (&self.0, context.global_layout, context.local_layout) {
+            (Var::Global(identifier), Some(layout), _) => {
+                let mut pieces = load_packed(context, layout, None)?;
+                pieces.extend(read_packed(layout.get(identifier)?));
+                Ok(pieces.join(OP_SEPARATOR))
+            }
+            (Var::Local(identifier), _, Some(layout)) => {
+                let account = pop_arg(prepared_stack)?.compiled;
+                let mut pieces = load_packed(context, layout, Some(&account))?;
+                pieces.extend(read_packed(layout.get(identifier)?));
+                Ok(pieces.join(OP_SEPARATOR))
+            }
+            (Var::Global(identifier), ..) => Ok(format!(
                 "{push_identifier}{OP_SEPARATOR}app_global_get",
                 push_identifier = Primitive::Byteslice(identifier.as_bytes().to_vec())
                     .compile(context, &mut Vec::new())?
             )),
-            Var::Local(identifier) => Ok(format!(
+            (Var::Local(identifier), ..) => Ok(format!(
                 "{account}{OP_SEPARATOR}{push_identifier}{OP_SEPARATOR}app_local_get", // app_local_get pops 2 elements (second is account identifier), which is why it is typed as a function instead of a simple primitive
-                account = prepared_stack.pop().ok_or(CompilationError::MissingStack)?,
+                account = pop_arg(prepared_stack)?.compiled,
                 push_identifier = Primitive::Byteslice(identifier.as_bytes().to_vec())
                     .compile(context, &mut Vec::new())?
             )),
-            Var::Bind(identifier) => {
-                let binding = context.scope.get(&identifier).ok_or::<CompilationError>(
+            (var @ (Var::Box(_) | Var::BoxMap(_)), ..) => {
+                let (mut pieces, primitive) = box_name(context, var, prepared_stack)?;
+                pieces.extend([
+                    "box_get".to_string(),
+                    with_error_message("assert", "box does not exist"),
+                ]);
+                if primitive == TypePrimitive::UInt64 {
+                    pieces.push("btoi".to_string());
+                }
+                Ok(pieces.join(OP_SEPARATOR))
+            }
+            (Var::Template(identifier), ..) => load_template(context.templates, identifier)
+                .ok_or_else(|| TypeError::UnboundIdentifier(self.0.clone()).into()),
+            (Var::Bind(identifier), ..) => {
+                let binding = context.scope.get(identifier).ok_or::<CompilationError>(
                     // should never happen if type checking is run before compilation
                     TypeError::UnboundIdentifier(self.0.clone()).into(),
                 )?;
@@ -1,16 +1,38 @@
-pub const MAX_TEAL_VERSION: u64 = 5;
-pub const OP_SEPARATOR: &'static str = "\n";
+pub const MAX_TEAL_VERSION: u64 = 10;
+/// Version of programs that do not declare one with `#pragma version`
+pub const DEFAULT_TEAL_VERSION: u64 = 5;
+pub const OP_SEPARATOR: &str = "\n";
 
+/// Appends `message` to a failing op as a comment, so that it can be reported
+/// when the program fails there (see [`app_spec::SourceInfo`]).
+pub(crate) fn with_error_message(op: &str, message: &str) -> String {
+    format!("{op} // {message}")
+}
+
+pub mod abi;
+pub mod address;
+pub mod app_spec;
+pub mod client;
 pub mod compilation_error;
 pub mod context;
 pub mod contract;
+pub mod description;
+pub mod event;
 pub mod expression;
 pub mod label;
+pub mod lsig;
 pub mod macros;
+pub mod method;
+pub mod opcode;
 pub mod program;
 pub mod struct_def;
+pub mod template;
 pub mod typing;
 
+// lets the generated client fixture refer to this crate by name
+#[cfg(test)]
+extern crate self as rusteal_ast;
+
 #[cfg(test)]
 mod tests {
     use crate::expression::apply::Apply;
@@ -18,6 +40,7 @@ mod tests {
     use crate::expression::bind::Bind;
     use crate::expression::cond::Cond;
     use crate::expression::constant::OnComplete;
+    use crate::expression::operator::Operator;
     use crate::expression::primitive::Primitive;
     use crate::expression::ret::Ret;
     use crate::expression::seq::Seq;
@@ -30,6 +53,8 @@ mod tests {
     fn test_seq_int_bytes() {
         let compiled = Program {
             version: 5,
+            methods: vec![],
+            mode: Default::default(),
             body: Expr::Seq(Box::new(Seq(
                 Expr::Primitive(Primitive::UInt64(5)),
                 Some(Expr::Primitive(Primitive::Byteslice(b"test".to_vec()))),
@@ -43,6 +68,8 @@ mod tests {
     fn let_binop_ret() {
         let program = Program {
             version: 5,
+            methods: vec![],
+            mode: Default::default(),
             body: bind_let!(
                 int_eq = binop!((int!(5)) == (int!(5)));
                 bind_let!(
@@ -62,6 +89,8 @@ mod tests {
     fn main_conditional() {
         let program = Program {
             version: 5,
+            methods: vec![],
+            mode: Default::default(),
             body: Expr::Seq(Box::new(Seq(
                 Expr::Cond(Box::new(Cond(
                     Expr::Apply(Box::new(Apply(
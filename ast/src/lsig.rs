@@ -0,0 +1,96 @@
+use crate::{
+    compilation_error::CompilationError,
+    context::{CompilationContext, Mode, TypeContext},
+    program::Program,
+    typing::TypeError,
+};
+
+/// A logic signature, e.g. `lsig escrow { ... }`, approving the transactions
+/// it signs for as an escrow account or as a delegated signature. Its program
+/// runs in signature mode, where application state, logs and inner
+/// transactions are unavailable, but its arguments are (`arg(i)`).
+pub struct LogicSig {
+    pub name: String,
+    pub program: Program,
+}
+
+impl LogicSig {
+    pub fn type_check(&self, context: &TypeContext) -> Result<(), TypeError> {
+        self.program.type_check_in(context)
+    }
+
+    pub fn compile(&self, context: &CompilationContext) -> Result<String, CompilationError> {
+        if self.program.mode != Mode::Signature {
+            return Err(CompilationError::UnsupportedMode(
+                format!("Logic signature {}", self.name),
+                Mode::Signature,
+                self.program.mode,
+            ));
+        }
+        self.program.compile_in(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply, binop,
+        compilation_error::CompilationError,
+        context::{CompilationContext, Mode},
+        expression::{
+            apply::Apply,
+            arg::Arg,
+            binary::Binary,
+            crypto::Crypto,
+            operator::Operator,
+            primitive::Primitive,
+            txn::Txn,
+            var::{RVal, Var},
+            Expr,
+        },
+        int,
+        program::Program,
+    };
+
+    use super::LogicSig;
+
+    fn lsig(body: Expr) -> LogicSig {
+        LogicSig {
+            name: "escrow".to_string(),
+            program: Program {
+                body,
+                mode: Mode::Signature,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test() {
+        let hash_lock = lsig(binop!(
+            (apply!(@fn Expr::Crypto(Crypto::Sha256); @arg apply!(@fn Expr::Arg(Arg); @arg int!(0))))
+                == (Expr::Txn(Txn::Note))
+        ));
+        hash_lock.type_check(&Default::default()).unwrap();
+        let compiled = hash_lock.compile(&CompilationContext::default()).unwrap();
+        println!("{compiled}");
+        assert_eq!(compiled, "#pragma version 5\narg 0\nsha256\ntxn Note\n==");
+    }
+
+    #[test]
+    fn application_only() {
+        let context = CompilationContext::default();
+        let state = lsig(Expr::RVal(RVal(Var::Global("counter".to_string()))));
+        assert!(matches!(
+            state.compile(&context),
+            Err(CompilationError::UnsupportedMode(opcode, Mode::Application, Mode::Signature))
+                if opcode == "app_global_get"
+        ));
+        let logged =
+            lsig(apply!(@fn Expr::Log(crate::expression::log::Log); @arg Expr::Txn(Txn::Note)));
+        assert!(matches!(
+            logged.compile(&context),
+            Err(CompilationError::UnsupportedMode(..))
+        ));
+    }
+}
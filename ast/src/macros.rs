@@ -2,8 +2,6 @@
 
 // trace_macros!(true);
 
-use crate::typing::TypeVar;
-
 #[macro_export]
 macro_rules! int {
     ($e: expr) => {
@@ -63,22 +61,43 @@ macro_rules! apply {
 #[macro_export]
 macro_rules! binop {
     (($a:expr) == ($b:expr)) => {
-        apply!(@fn Expr::Binary(Binary::Equals); @arg $b; @arg $a)
+        apply!(@fn Expr::Operator(Operator::new(Binary::Equals)); @arg $b; @arg $a)
     };
     (($a:expr) != ($b:expr)) => {
-        apply!(@fn Expr::Binary(Binary::NotEquals); @arg $b; @arg $a)
+        apply!(@fn Expr::Operator(Operator::new(Binary::NotEquals)); @arg $b; @arg $a)
     };
     (($a:expr) > ($b:expr)) => {
-        apply!(@fn Expr::Binary(Binary::GreaterThan); @arg $b; @arg $a)
+        apply!(@fn Expr::Operator(Operator::new(Binary::GreaterThan)); @arg $b; @arg $a)
     };
     (($a:expr) >= ($b:expr)) => {
-        apply!(@fn Expr::Binary(Binary::GreaterThanEquals); @arg $b; @arg $a)
+        apply!(@fn Expr::Operator(Operator::new(Binary::GreaterThanEquals)); @arg $b; @arg $a)
     };
     (($a:expr) < ($b:expr)) => {
-        apply!(@fn Expr::Binary(Binary::LessThan); @arg $b; @arg $a)
+        apply!(@fn Expr::Operator(Operator::new(Binary::LessThan)); @arg $b; @arg $a)
     };
     (($a:expr) <= ($b:expr)) => {
-        apply!(@fn Expr::Binary(Binary::LessThanEquals); @arg $b; @arg $a)
+        apply!(@fn Expr::Operator(Operator::new(Binary::LessThanEquals)); @arg $b; @arg $a)
+    };
+    (($a:expr) + ($b:expr)) => {
+        apply!(@fn Expr::Operator(Operator::new(Binary::Add)); @arg $b; @arg $a)
+    };
+    (($a:expr) - ($b:expr)) => {
+        apply!(@fn Expr::Operator(Operator::new(Binary::Subtract)); @arg $b; @arg $a)
+    };
+    (($a:expr) * ($b:expr)) => {
+        apply!(@fn Expr::Operator(Operator::new(Binary::Multiply)); @arg $b; @arg $a)
+    };
+    (($a:expr) / ($b:expr)) => {
+        apply!(@fn Expr::Operator(Operator::new(Binary::Divide)); @arg $b; @arg $a)
+    };
+    (($a:expr) % ($b:expr)) => {
+        apply!(@fn Expr::Operator(Operator::new(Binary::Modulo)); @arg $b; @arg $a)
+    };
+    (($a:expr) && ($b:expr)) => {
+        apply!(@fn Expr::Operator(Operator::new(Binary::And)); @arg $b; @arg $a)
+    };
+    (($a:expr) || ($b:expr)) => {
+        apply!(@fn Expr::Operator(Operator::new(Binary::Or)); @arg $b; @arg $a)
     };
 }
 
@@ -196,6 +215,7 @@ macro_rules! ret {
 ///     <typekw> ::
 ///           int
 ///         | bytes
+///         | biguint
 ///         | void
 ///         | halt
 /// ```
@@ -232,6 +252,9 @@ macro_rules! typesig {
     (@_context $t:ident bytes) => {
         TypeEnum::Simple(TypePrimitive::Byteslice)
     };
+    (@_context $t:ident biguint) => {
+        TypeEnum::Simple(TypePrimitive::BigUInt)
+    };
     (@_context $t:ident void) => {
         TypeEnum::Simple(TypePrimitive::Void)
     };
@@ -299,8 +322,8 @@ mod tests {
     use crate::expression::apply::Apply;
     use crate::expression::binary::Binary;
     use crate::expression::bind::Bind;
-    use crate::expression::cond::Cond;
     use crate::expression::if_else::If;
+    use crate::expression::operator::Operator;
     use crate::expression::primitive::Primitive;
     use crate::expression::ret::Ret;
     use crate::expression::seq::Seq;
@@ -326,7 +349,7 @@ mod tests {
         // let x = seq!(int!(1) ; int!(3) ; int!(2));
         // println!("{:?}", x);
 
-        // let x = apply!(@fn Expr::Binary(Binary::Equals); @arg int!(1); @arg int!(2));
+        // let x = apply!(@fn Expr::Operator(Operator::new(Binary::Equals)); @arg int!(1); @arg int!(2));
         // println!("{:?}", x);
 
         // let x = binop!((int!(2)) > (int!(1)));
@@ -334,18 +357,27 @@ mod tests {
 
         let x = bind_let!(my_scratch = binop!((int!(2)) > (int!(1))); seq! {
             assign!(@local my_local[int!(0)] = int!(1));
-            val!(@local my_local[int!(0)]);
-            assign!(@global my_global = int!(2));
-            val!(@global my_global);
-            assign!(@scratch my_scratch = int!(2));
-            val!(@scratch my_scratch);
-            r#if!(
+            assign!(@global my_global = val!(@local my_local[int!(0)]));
+            assign!(@scratch my_scratch = val!(@global my_global));
+            ret!(r#if!(
                 (binop!((val!(@scratch my_scratch)) > (int!(4))))
-                @then bytes!(">4".into());
-                @else bytes!("<=4".into());
-            );
-            ret!(int!(1));
+                @then int!(1);
+                @else int!(0);
+            ));
         });
+        let scope = |name: &str| {
+            std::rc::Rc::new(
+                [(name.to_string(), TypeEnum::Simple(TypePrimitive::UInt64))]
+                    .into_iter()
+                    .collect(),
+            )
+        };
+        x.resolve(&crate::context::TypeContext {
+            global_scope: scope("my_global"),
+            local_scope: scope("my_local"),
+            ..Default::default()
+        })
+        .unwrap();
         println!("{}", x.compile_raw().unwrap());
 
         // let x = cond!(
@@ -0,0 +1,397 @@
+use crate::{
+    abi::{decode, encode, selector, AbiType},
+    app_spec::Actions,
+    compilation_error::CompilationError,
+    context::{CompilationBinding, CompilationContext, TypeContext},
+    expression::{constant::OnComplete, primitive::Primitive, Expr, Expression},
+    label::create_label_id,
+    typesig,
+    typing::{TypeEnum, TypeError, TypePrimitive, TypeVar},
+    with_error_message, OP_SEPARATOR,
+};
+
+/// Prefix of logged ARC-4 return values.
+pub const RETURN_PREFIX: [u8; 4] = [0x15, 0x1f, 0x7c, 0x75];
+
+/// Application arguments after the selector; ARC-4 packs any further
+/// arguments into a tuple, which is not supported.
+pub const MAX_METHOD_ARGS: usize = 15;
+
+/// An ARC-4 method, e.g. `abi fn add(a: uint64, b: uint64) -> uint64 { a + b }`,
+/// called by passing its selector as the first application argument.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbiMethod {
+    pub name: String,
+    pub args: Vec<(String, AbiType)>,
+    pub returns: Option<AbiType>,
+    pub body: Expr,
+    /// Doc comment from source, carried into the contract description
+    pub doc: Option<String>,
+    /// Whether the method creates the application (`#[create]`) instead of
+    /// being called on an existing one
+    pub create: bool,
+    /// Allowed on-completion actions (`#[opt_in]`, `#[delete]`, ...), only
+    /// `NoOp` if empty
+    pub on_complete: Vec<OnComplete>,
+    /// Whether only the application's creator may call the method
+    /// (`#[update(auth = creator)]`)
+    pub creator_only: bool,
+    /// Whether the method leaves the application's state unchanged
+    /// (`#[readonly]`), so that clients may simulate instead of sending it
+    pub readonly: bool,
+}
+
+impl AbiMethod {
+    /// Signature used to derive the selector, e.g. `add(uint64,uint64)uint64`.
+    pub fn signature(&self) -> String {
+        let args = self
+            .args
+            .iter()
+            .map(|(_, abi_type)| abi_type.to_string())
+            .collect::<Vec<_>>();
+        let returns = self
+            .returns
+            .as_ref()
+            .map_or("void".to_string(), |r| r.to_string());
+        format!("{}({}){}", self.name, args.join(","), returns)
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        selector(&self.signature())
+    }
+
+    pub fn allowed_on_complete(&self) -> Vec<OnComplete> {
+        match self.on_complete.is_empty() {
+            true => vec![OnComplete::NoOp],
+            false => self.on_complete.clone(),
+        }
+    }
+
+    /// On-completion actions allowed when creating the application and when
+    /// calling it once created.
+    pub fn actions(&self) -> (Vec<OnComplete>, Vec<OnComplete>) {
+        match self.create {
+            true => (self.allowed_on_complete(), vec![]),
+            false => (vec![], self.allowed_on_complete()),
+        }
+    }
+
+    /// Rejects calls with other on-completion actions, on the wrong side of
+    /// creation or by anyone but the creator if restricted.
+    fn compile_checks(
+        &self,
+        context: &CompilationContext,
+    ) -> Result<Vec<String>, CompilationError> {
+        let allowed = self.allowed_on_complete();
+        for on_complete in &allowed {
+            let creatable = matches!(
+                on_complete,
+                OnComplete::NoOp | OnComplete::OptIn | OnComplete::DeleteApplication
+            );
+            if *on_complete == OnComplete::ClearState || (self.create && !creatable) {
+                return Err(CompilationError::InvalidOnComplete(
+                    self.signature(),
+                    *on_complete,
+                ));
+            }
+        }
+        let mut pieces = assert_on_complete(context, &allowed)?;
+        pieces.push("txn ApplicationID".to_string());
+        if self.create {
+            pieces.push("!".to_string());
+            pieces.push(with_error_message("assert", "can only create"));
+        } else {
+            pieces.push(with_error_message("assert", "cannot create"));
+        }
+        if self.creator_only {
+            pieces.extend([
+                "txn Sender".to_string(),
+                "global CreatorAddress".to_string(),
+                "==".to_string(),
+                with_error_message("assert", "sender is not the creator"),
+            ]);
+        }
+        Ok(pieces)
+    }
+
+    /// Type checks the body with the decoded arguments bound.
+    pub fn type_check(&self, context: &TypeContext) -> Result<(), TypeError> {
+        let bindings = self
+            .args
+            .iter()
+            .map(|(identifier, abi_type)| (identifier.clone(), abi_type.stack_type()))
+            .collect();
+        let context = context.with_binds(bindings);
+        let mut returns = self
+            .returns
+            .as_ref()
+            .map_or(typesig!(void), |r| r.stack_type());
+        self.body.resolve(&context)?.unify(&mut returns)?;
+        Ok(())
+    }
+
+    /// Decodes the arguments into scratch space, runs the body, logs the
+    /// encoded return value and approves the call.
+    fn compile(&self, context: &CompilationContext) -> Result<String, CompilationError> {
+        if self.args.len() > MAX_METHOD_ARGS {
+            return Err(CompilationError::TooManyMethodArgs(self.signature()));
+        }
+        let first_scratch_id = context.scratch_id as usize;
+        let next_scratch_id = u8::try_from(first_scratch_id + self.args.len())
+            .map_err(|_| CompilationError::OutOfScratchSpace)?;
+
+        let mut pieces = self.compile_checks(context)?;
+        let mut bindings = vec![];
+        for (i, (identifier, abi_type)) in self.args.iter().enumerate() {
+            let scratch_id = (first_scratch_id + i) as u8;
+            pieces.push(format!("txna ApplicationArgs {}", i + 1));
+            pieces.extend(decode(abi_type));
+            pieces.push(format!("store {scratch_id}"));
+            bindings.push((
+                identifier.clone(),
+                CompilationBinding::ScratchVar(scratch_id),
+            ));
+        }
+        let context = CompilationContext {
+            scope: context.scope.add_all(bindings),
+            scratch_id: next_scratch_id,
+            ..*context
+        };
+        pieces.push(self.body.compile(&context, &mut vec![])?);
+        if let Some(returns) = &self.returns {
+            pieces.extend(encode(returns));
+            pieces.extend([
+                Primitive::Byteslice(RETURN_PREFIX.to_vec()).compile(&context, &mut vec![])?,
+                "swap".to_string(),
+                "concat".to_string(),
+                "log".to_string(),
+            ]);
+        }
+        pieces.extend(["int 1".to_string(), "return".to_string()]);
+        Ok(pieces.join(OP_SEPARATOR))
+    }
+}
+
+/// Asserts that the OnCompletion of the call is one of `allowed`.
+fn assert_on_complete(
+    context: &CompilationContext,
+    allowed: &[OnComplete],
+) -> Result<Vec<String>, CompilationError> {
+    let mut pieces = vec![];
+    for (i, on_complete) in allowed.iter().enumerate() {
+        pieces.extend([
+            "txn OnCompletion".to_string(),
+            on_complete.compile(context, &mut vec![])?,
+            "==".to_string(),
+        ]);
+        if i > 0 {
+            pieces.push("||".to_string());
+        }
+    }
+    pieces.push(with_error_message("assert", "OnCompletion not allowed"));
+    Ok(pieces)
+}
+
+/// OnCompletion actions allowed for bare calls, i.e. calls without
+/// application arguments. Without ABI methods the body handles every action
+/// but `ClearState`; otherwise [`compile_router`] only lets `NoOp` calls run
+/// the body, which may also create the application.
+pub fn bare_actions(methods: &[AbiMethod]) -> Actions {
+    if methods.is_empty() {
+        Actions {
+            create: vec![
+                OnComplete::NoOp,
+                OnComplete::OptIn,
+                OnComplete::DeleteApplication,
+            ],
+            call: vec![
+                OnComplete::NoOp,
+                OnComplete::OptIn,
+                OnComplete::CloseOut,
+                OnComplete::UpdateApplication,
+                OnComplete::DeleteApplication,
+            ],
+        }
+    } else {
+        Actions {
+            create: vec![OnComplete::NoOp],
+            call: vec![OnComplete::NoOp],
+        }
+    }
+}
+
+/// Dispatches on the selector in the first application argument, rejecting
+/// unknown selectors. Calls without arguments run `bare` instead, if their
+/// OnCompletion is one of the [`bare_actions`].
+pub(crate) fn compile_router(
+    context: &CompilationContext,
+    methods: &[AbiMethod],
+    bare: String,
+) -> Result<String, CompilationError> {
+    context.require_version("ABI methods", 5)?;
+    let selectors = methods.iter().map(AbiMethod::selector).collect::<Vec<_>>();
+    for (i, method) in methods.iter().enumerate() {
+        if selectors[..i].contains(&selectors[i]) {
+            return Err(CompilationError::DuplicateMethod(method.signature()));
+        }
+    }
+    let labels = methods
+        .iter()
+        .map(|method| format!("method_{}{}", method.name, create_label_id()))
+        .collect::<Vec<_>>();
+    let bare_label = format!("bare{}", create_label_id());
+
+    let mut pieces = vec!["txn NumAppArgs".to_string(), format!("bz {bare_label}")];
+    if context.version >= 8 {
+        for selector in &selectors {
+            pieces.push(Primitive::Byteslice(selector.to_vec()).compile(context, &mut vec![])?);
+        }
+        pieces.push("txna ApplicationArgs 0".to_string());
+        pieces.push(format!("match {}", labels.join(" ")));
+    } else {
+        for (selector, label) in selectors.iter().zip(&labels) {
+            pieces.extend([
+                "txna ApplicationArgs 0".to_string(),
+                Primitive::Byteslice(selector.to_vec()).compile(context, &mut vec![])?,
+                "==".to_string(),
+                format!("bnz {label}"),
+            ]);
+        }
+    }
+    pieces.push(with_error_message("err", "unknown method selector"));
+    for (method, label) in methods.iter().zip(&labels) {
+        pieces.push(format!("{label}:"));
+        pieces.push(method.compile(context)?);
+    }
+    pieces.push(format!("{bare_label}:"));
+    pieces.extend(assert_on_complete(context, &bare_actions(methods).call)?);
+    pieces.push(bare);
+    Ok(pieces.join(OP_SEPARATOR))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        abi::AbiType,
+        apply, binop,
+        compilation_error::CompilationError,
+        context::TypeContext,
+        expression::{
+            apply::Apply,
+            binary::Binary,
+            constant::OnComplete,
+            operator::Operator,
+            primitive::Primitive,
+            var::{RVal, Var},
+            Expr,
+        },
+        int,
+        program::Program,
+    };
+
+    use super::AbiMethod;
+
+    fn add() -> AbiMethod {
+        AbiMethod {
+            name: "add".to_string(),
+            args: vec![
+                ("a".to_string(), AbiType::Uint(64)),
+                ("b".to_string(), AbiType::Uint(64)),
+            ],
+            returns: Some(AbiType::Uint(64)),
+            body: binop!(
+                (Expr::RVal(RVal(Var::Bind("a".to_string()))))
+                    + (Expr::RVal(RVal(Var::Bind("b".to_string()))))
+            ),
+            doc: None,
+            create: false,
+            on_complete: vec![],
+            creator_only: false,
+            readonly: false,
+        }
+    }
+
+    #[test]
+    fn test() {
+        let method = add();
+        assert_eq!(method.signature(), "add(uint64,uint64)uint64");
+        method.type_check(&TypeContext::default()).unwrap();
+
+        let program = Program {
+            version: 8,
+            methods: vec![method],
+            ..Default::default()
+        };
+        let compiled = program.compile().unwrap();
+        println!("{compiled}");
+        assert!(compiled.contains("txna ApplicationArgs 0\nmatch "));
+        assert!(compiled.contains("txna ApplicationArgs 1\nbtoi\nstore 0"));
+        assert!(compiled.contains("itob\nbyte 0x151f7c75\nswap\nconcat\nlog\nint 1\nreturn"));
+
+        let program = Program {
+            version: 7,
+            ..program
+        };
+        assert!(!program.compile().unwrap().contains("match"));
+    }
+
+    #[test]
+    fn mistyped_body() {
+        let method = AbiMethod {
+            returns: Some(AbiType::String),
+            ..add()
+        };
+        assert!(method.type_check(&TypeContext::default()).is_err());
+        let method = AbiMethod {
+            body: int!(1),
+            returns: None,
+            ..add()
+        };
+        assert!(method.type_check(&TypeContext::default()).is_err());
+    }
+
+    #[test]
+    fn lifecycle() {
+        let create = AbiMethod {
+            name: "create".to_string(),
+            create: true,
+            on_complete: vec![OnComplete::OptIn],
+            ..add()
+        };
+        assert_eq!(create.actions(), (vec![OnComplete::OptIn], vec![]));
+        let upgrade = AbiMethod {
+            name: "upgrade".to_string(),
+            on_complete: vec![OnComplete::UpdateApplication, OnComplete::DeleteApplication],
+            creator_only: true,
+            ..add()
+        };
+        let program = Program {
+            methods: vec![create, upgrade],
+            ..Default::default()
+        };
+        program.type_check().unwrap();
+        let compiled = program.compile().unwrap();
+        println!("{compiled}");
+        assert!(compiled.contains(
+            "txn OnCompletion\nint OptIn\n==\nassert // OnCompletion not allowed\ntxn ApplicationID\n!\nassert // can only create"
+        ));
+        assert!(compiled.contains(
+            "int UpdateApplication\n==\ntxn OnCompletion\nint DeleteApplication\n==\n||\nassert // OnCompletion not allowed\ntxn ApplicationID\nassert // cannot create\ntxn Sender\nglobal CreatorAddress\n==\nassert // sender is not the creator"
+        ));
+        assert!(compiled
+            .contains("txn OnCompletion\nint NoOp\n==\nassert // OnCompletion not allowed\nint 0"));
+
+        let program = Program {
+            methods: vec![AbiMethod {
+                create: true,
+                on_complete: vec![OnComplete::CloseOut],
+                ..add()
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            program.compile(),
+            Err(CompilationError::InvalidOnComplete(_, OnComplete::CloseOut))
+        ));
+    }
+}
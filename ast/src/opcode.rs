@@ -0,0 +1,131 @@
+use crate::{compilation_error::CompilationError, context::Mode};
+
+/// An opcode, or a field of `global`, that is only available in one
+/// execution mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpcodeMode {
+    pub name: &'static str,
+    pub mode: Mode,
+}
+
+const fn app(name: &'static str) -> OpcodeMode {
+    OpcodeMode {
+        name,
+        mode: Mode::Application,
+    }
+}
+
+const fn sig(name: &'static str) -> OpcodeMode {
+    OpcodeMode {
+        name,
+        mode: Mode::Signature,
+    }
+}
+
+/// Mode metadata of the AVM opcode table, listing the opcodes and `global`
+/// fields that are not available in both modes. `ed25519verify`, which was
+/// signature-only before v5, is checked where it is compiled.
+pub const MODE_RESTRICTED: &[OpcodeMode] = &[
+    sig("arg"),
+    sig("arg_0"),
+    sig("arg_1"),
+    sig("arg_2"),
+    sig("arg_3"),
+    sig("args"),
+    app("balance"),
+    app("min_balance"),
+    app("app_opted_in"),
+    app("app_local_get"),
+    app("app_local_get_ex"),
+    app("app_global_get"),
+    app("app_global_get_ex"),
+    app("app_local_put"),
+    app("app_global_put"),
+    app("app_local_del"),
+    app("app_global_del"),
+    app("asset_holding_get"),
+    app("asset_params_get"),
+    app("app_params_get"),
+    app("acct_params_get"),
+    app("gload"),
+    app("gloads"),
+    app("gloadss"),
+    app("gaid"),
+    app("gaids"),
+    app("log"),
+    app("itxn_begin"),
+    app("itxn_next"),
+    app("itxn_field"),
+    app("itxn_submit"),
+    app("itxn"),
+    app("itxna"),
+    app("itxnas"),
+    app("gitxn"),
+    app("gitxna"),
+    app("gitxnas"),
+    app("box_create"),
+    app("box_extract"),
+    app("box_replace"),
+    app("box_del"),
+    app("box_len"),
+    app("box_get"),
+    app("box_put"),
+    app("box_resize"),
+    app("box_splice"),
+    app("global CurrentApplicationID"),
+    app("global CreatorAddress"),
+    app("global CurrentApplicationAddress"),
+    app("global CallerApplicationID"),
+    app("global CallerApplicationAddress"),
+];
+
+pub fn opcode_mode(name: &str) -> Option<Mode> {
+    MODE_RESTRICTED
+        .iter()
+        .find(|op| op.name == name)
+        .map(|op| op.mode)
+}
+
+/// Rejects compiled TEAL that uses opcodes unavailable in `mode`.
+pub(crate) fn check_mode(teal: &str, mode: Mode) -> Result<(), CompilationError> {
+    for line in teal.lines() {
+        let mut tokens = line.split_whitespace();
+        let Some(opcode) = tokens.next() else {
+            continue;
+        };
+        let with_field = tokens.next().map(|field| format!("{opcode} {field}"));
+        for name in [Some(opcode.to_string()), with_field].into_iter().flatten() {
+            match opcode_mode(&name) {
+                Some(required) if required != mode => {
+                    return Err(CompilationError::UnsupportedMode(name, required, mode))
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{compilation_error::CompilationError, context::Mode};
+
+    use super::{check_mode, opcode_mode};
+
+    #[test]
+    fn test() {
+        assert_eq!(opcode_mode("app_global_put"), Some(Mode::Application));
+        assert_eq!(opcode_mode("arg"), Some(Mode::Signature));
+        assert_eq!(opcode_mode("sha256"), None);
+
+        let teal = "#pragma version 8\nglobal GroupSize\nint 1\n==\nlabel1:\nbyte \"log\"";
+        assert!(check_mode(teal, Mode::Signature).is_ok());
+        assert!(check_mode(teal, Mode::Application).is_ok());
+        assert!(matches!(
+            check_mode("global CreatorAddress\nlen", Mode::Signature),
+            Err(CompilationError::UnsupportedMode(name, Mode::Application, Mode::Signature))
+                if name == "global CreatorAddress"
+        ));
+        assert!(check_mode("arg 0", Mode::Application).is_err());
+    }
+}
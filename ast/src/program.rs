@@ -1,36 +1,72 @@
 use crate::{
     compilation_error::CompilationError,
-    context::{CompilationContext, TypeContext},
+    context::{CompilationContext, Mode, TypeContext},
     expression::{primitive::Primitive, Expr, Expression},
+    method::{compile_router, AbiMethod},
+    opcode::check_mode,
+    template::constant_blocks,
     typing::TypeError,
-    MAX_TEAL_VERSION, OP_SEPARATOR,
+    DEFAULT_TEAL_VERSION, OP_SEPARATOR,
 };
 
 pub struct Program {
     pub version: u64,
     pub body: Expr,
+    /// ARC-4 methods; when present, `body` only handles bare calls
+    pub methods: Vec<AbiMethod>,
+    pub mode: Mode,
 }
 
 impl Default for Program {
     fn default() -> Self {
         Program {
-            version: MAX_TEAL_VERSION,
+            version: DEFAULT_TEAL_VERSION,
             body: Expr::Primitive(Primitive::UInt64(0)),
+            methods: vec![],
+            mode: Mode::default(),
         }
     }
 }
 
 impl Program {
     pub fn type_check(&self) -> Result<(), TypeError> {
-        let resolution = self.body.resolve(&TypeContext::default())?;
-        println!("{:?}", resolution);
-        Ok(())
+        self.type_check_in(&TypeContext::default())
+    }
+
+    /// Type checks the program with state variables bound in `context`.
+    pub fn type_check_in(&self, context: &TypeContext) -> Result<(), TypeError> {
+        self.body.resolve(context)?;
+        self.methods
+            .iter()
+            .try_for_each(|method| method.type_check(context))
     }
 
     pub fn compile(&self) -> Result<String, CompilationError> {
+        self.compile_in(&CompilationContext::default())
+    }
+
+    /// Compiles the program with the state layouts of `context`, targeting
+    /// the program's own version and mode.
+    pub fn compile_in(&self, context: &CompilationContext) -> Result<String, CompilationError> {
         let version = self.version;
-        self.body
-            .compile(&CompilationContext::default(), &mut vec![])
-            .map(|compiled| format!("#pragma version {version}{OP_SEPARATOR}{compiled}"))
+        let context = CompilationContext {
+            scope: context.scope.clone(),
+            version,
+            mode: self.mode,
+            ..*context
+        };
+        let mut compiled = self.body.compile(&context, &mut vec![])?;
+        if !self.methods.is_empty() {
+            context.require_mode("ABI methods", Mode::Application)?;
+            compiled = compile_router(&context, &self.methods, compiled)?;
+        }
+        check_mode(&compiled, self.mode)?;
+        if !context.templates.is_empty() {
+            compiled = format!(
+                "{}{OP_SEPARATOR}{compiled}",
+                constant_blocks(context.templates)
+            );
+        }
+        Ok(format!("#pragma version {version}{OP_SEPARATOR}{compiled}"))
     }
 }
@@ -1,8 +1,165 @@
 use std::collections::HashMap;
 
-use crate::typing::TypePrimitive;
+use crate::{
+    compilation_error::CompilationError,
+    context::Scope,
+    typing::{TypeEnum, TypePrimitive},
+};
+
+/// Maximum length of a state key, in bytes.
+pub const MAX_KEY_LENGTH: usize = 64;
+/// Maximum combined length of a state key and its byteslice value, in bytes.
+pub const MAX_KEY_VALUE_LENGTH: usize = 128;
+pub const MAX_GLOBAL_KEYS: u64 = 64;
+pub const MAX_LOCAL_KEYS: u64 = 16;
+
+/// State key holding all fields of a packed schema.
+pub const PACKED_KEY: &str = "packed";
 
 #[derive(Default)]
 pub struct StructDef<'a> {
     pub fields: HashMap<&'a str, TypePrimitive>,
+    /// Names of `fields` in declaration order, which fixes the layout of
+    /// packed schemas. Fields of a deployed packed schema may only be appended.
+    pub order: Vec<&'a str>,
+    /// Byte widths of fixed-width byteslice fields, e.g. `owner: bytes[32]`
+    pub widths: HashMap<&'a str, usize>,
+    /// Key and value types of box maps, e.g. `balances: map<bytes, uint64>`,
+    /// only allowed in `schema box`
+    pub maps: HashMap<&'a str, (TypePrimitive, TypePrimitive)>,
+    /// Whether all fields are serialized into a single byteslice key
+    /// ([`PACKED_KEY`]) instead of using a key each
+    pub packed: bool,
+}
+
+/// Position of a field within the value of a packed schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedField {
+    pub offset: usize,
+    pub width: usize,
+    pub primitive: TypePrimitive,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedLayout {
+    pub key: String,
+    pub fields: HashMap<String, PackedField>,
+    pub size: usize,
+}
+
+impl PackedLayout {
+    pub fn get(&self, field: &str) -> Result<&PackedField, CompilationError> {
+        self.fields
+            .get(field)
+            .ok_or_else(|| CompilationError::UnpackableField(field.to_string()))
+    }
+}
+
+/// Number of state slots of each kind that must be allocated when creating the
+/// application.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StateSchema {
+    pub num_uints: u64,
+    pub num_byte_slices: u64,
+}
+
+impl<'a> StructDef<'a> {
+    /// Bindings for the declared fields, used to type check state accesses.
+    pub fn type_scope<'b>(&self) -> Scope<'b, String, TypeEnum> {
+        self.fields
+            .iter()
+            .map(|(name, primitive)| (name.to_string(), TypeEnum::Simple(primitive.clone())))
+            .chain(self.maps.iter().map(|(name, (key, value))| {
+                (
+                    name.to_string(),
+                    TypeEnum::Arrow(
+                        Box::new(TypeEnum::Simple(key.clone())),
+                        Box::new(TypeEnum::Simple(value.clone())),
+                    ),
+                )
+            }))
+            .collect()
+    }
+
+    /// Assigns each field of a packed schema a fixed position, in declaration
+    /// order. Returns `None` for unpacked schemas.
+    pub fn packed_layout(&self) -> Result<Option<PackedLayout>, CompilationError> {
+        if !self.packed {
+            return Ok(None);
+        }
+        if let Some(name) = self.fields.keys().find(|k| !self.order.contains(k)) {
+            return Err(CompilationError::UnpackableField(name.to_string()));
+        }
+        let mut fields = HashMap::new();
+        let mut size = 0;
+        for name in &self.order {
+            let primitive = self
+                .fields
+                .get(name)
+                .ok_or_else(|| CompilationError::UnpackableField(name.to_string()))?
+                .clone();
+            let width = match (&primitive, self.widths.get(name)) {
+                (TypePrimitive::UInt64, None) => 8,
+                (TypePrimitive::Byteslice, Some(width)) => *width,
+                _ => return Err(CompilationError::UnpackableField(name.to_string())),
+            };
+            fields.insert(
+                name.to_string(),
+                PackedField {
+                    offset: size,
+                    width,
+                    primitive,
+                },
+            );
+            size += width;
+        }
+        if PACKED_KEY.len() + size > MAX_KEY_VALUE_LENGTH {
+            return Err(CompilationError::StateEntryTooLarge(
+                PACKED_KEY.to_string(),
+                size,
+            ));
+        }
+        Ok(Some(PackedLayout {
+            key: PACKED_KEY.to_string(),
+            fields,
+            size,
+        }))
+    }
+
+    pub fn state_schema(&self) -> StateSchema {
+        if self.packed {
+            return StateSchema {
+                num_uints: 0,
+                num_byte_slices: u64::from(!self.fields.is_empty()),
+            };
+        }
+
+        let num_uints = self
+            .fields
+            .values()
+            .filter(|primitive| **primitive == TypePrimitive::UInt64)
+            .count() as u64;
+        StateSchema {
+            num_uints,
+            num_byte_slices: self.fields.len() as u64 - num_uints,
+        }
+    }
+
+    /// Computes the schema, checking it against the protocol limits. `name`
+    /// identifies the schema (`global` or `local`) in error messages.
+    pub fn validate(&self, name: &str, max_keys: u64) -> Result<StateSchema, CompilationError> {
+        if let Some(key) = self.fields.keys().find(|k| k.len() > MAX_KEY_LENGTH) {
+            return Err(CompilationError::KeyTooLong(key.to_string()));
+        }
+        let schema = self.state_schema();
+        let keys = schema.num_uints + schema.num_byte_slices;
+        if keys > max_keys {
+            return Err(CompilationError::TooManyStateKeys(
+                name.to_string(),
+                keys,
+                max_keys,
+            ));
+        }
+        Ok(schema)
+    }
 }
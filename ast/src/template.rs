@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+
+use crate::{compilation_error::CompilationError, typing::TypePrimitive, OP_SEPARATOR};
+
+/// Prefix of the placeholders that template variables compile to.
+pub const TEMPLATE_PREFIX: &str = "TMPL_";
+
+const INTCBLOCK: u8 = 0x20;
+const BYTECBLOCK: u8 = 0x26;
+
+/// A deploy-time parameter, e.g. `template EXPIRY: uint64`, read with
+/// `template.EXPIRY`. Programs of a contract declaring templates start with
+/// an `intcblock` and a `bytecblock` holding one `TMPL_` placeholder per
+/// template, in declaration order, which are substituted before deploying.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pub name: String,
+    pub primitive: TypePrimitive,
+}
+
+impl Template {
+    pub fn placeholder(&self) -> String {
+        format!("{TEMPLATE_PREFIX}{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateValue {
+    Uint(u64),
+    Bytes(Vec<u8>),
+}
+
+impl From<u64> for TemplateValue {
+    fn from(value: u64) -> Self {
+        TemplateValue::Uint(value)
+    }
+}
+
+impl From<&[u8]> for TemplateValue {
+    fn from(value: &[u8]) -> Self {
+        TemplateValue::Bytes(value.to_vec())
+    }
+}
+
+impl From<&str> for TemplateValue {
+    fn from(value: &str) -> Self {
+        value.as_bytes().into()
+    }
+}
+
+fn block(templates: &[Template], primitive: TypePrimitive) -> impl Iterator<Item = &Template> {
+    templates.iter().filter(move |t| t.primitive == primitive)
+}
+
+/// Loads `name` from the constant block holding the templates of its type.
+pub(crate) fn load_template(templates: &[Template], name: &str) -> Option<String> {
+    let template = templates.iter().find(|t| t.name == name)?;
+    let index = block(templates, template.primitive.clone()).position(|t| t.name == name)?;
+    let op = match template.primitive {
+        TypePrimitive::UInt64 => "intc",
+        _ => "bytec",
+    };
+    Some(format!("{op} {index} // {}", template.placeholder()))
+}
+
+/// Constant blocks declaring the placeholders of `templates`. Both blocks are
+/// always emitted, so that the assembler does not add constants to them.
+pub(crate) fn constant_blocks(templates: &[Template]) -> String {
+    let placeholders = |primitive| {
+        block(templates, primitive)
+            .map(|t| format!(" {}", t.placeholder()))
+            .collect::<String>()
+    };
+    format!(
+        "intcblock{}{OP_SEPARATOR}bytecblock{}",
+        placeholders(TypePrimitive::UInt64),
+        placeholders(TypePrimitive::Byteslice)
+    )
+}
+
+fn value<'a>(
+    template: &Template,
+    values: &'a BTreeMap<String, TemplateValue>,
+) -> Result<&'a TemplateValue, CompilationError> {
+    let value = values
+        .get(&template.name)
+        .ok_or_else(|| CompilationError::MissingTemplateValue(template.name.clone()))?;
+    match (&template.primitive, value) {
+        (TypePrimitive::UInt64, TemplateValue::Uint(_))
+        | (TypePrimitive::Byteslice, TemplateValue::Bytes(_)) => Ok(value),
+        _ => Err(CompilationError::TemplateTypeMismatch(
+            template.name.clone(),
+        )),
+    }
+}
+
+/// Replaces the placeholders of `templates` in `teal` with `values`. Each
+/// line is substituted in place, so TEAL line numbers stay valid.
+pub fn substitute_teal(
+    teal: &str,
+    templates: &[Template],
+    values: &BTreeMap<String, TemplateValue>,
+) -> Result<String, CompilationError> {
+    let literals = templates
+        .iter()
+        .map(|template| {
+            let literal = match value(template, values)? {
+                TemplateValue::Uint(i) => i.to_string(),
+                TemplateValue::Bytes(bytes) => format!("0x{}", hex(bytes)),
+            };
+            Ok((template.placeholder(), literal))
+        })
+        .collect::<Result<BTreeMap<_, _>, CompilationError>>()?;
+    Ok(teal
+        .lines()
+        .map(|line| {
+            let (code, comment) = match line.split_once(" // ") {
+                Some((code, comment)) => (code, Some(comment)),
+                None => (line, None),
+            };
+            let code = code
+                .split(' ')
+                .map(|token| literals.get(token).map_or(token, String::as_str))
+                .collect::<Vec<_>>()
+                .join(" ");
+            match comment {
+                Some(comment) => format!("{code} // {comment}"),
+                None => code,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(OP_SEPARATOR))
+}
+
+/// Replaces the template entries of the constant blocks of an assembled
+/// program, e.g. one assembled with placeholder values, with `values`. Only
+/// the constant blocks change size; the code following them is unchanged, so
+/// offsets measured from the end of the blocks (ARC-56 `cblocks`) stay valid.
+pub fn substitute_bytecode(
+    program: &[u8],
+    templates: &[Template],
+    values: &BTreeMap<String, TemplateValue>,
+) -> Result<Vec<u8>, CompilationError> {
+    let mut reader = Reader { program, pos: 0 };
+    let version = reader.varuint()?;
+    let mut patched = vec![];
+    write_varuint(&mut patched, version);
+    while let Some(&op) = program.get(reader.pos) {
+        if op != INTCBLOCK && op != BYTECBLOCK {
+            break;
+        }
+        reader.pos += 1;
+        let count = reader.varuint()?;
+        let mut entries = (0..count)
+            .map(|_| match op {
+                INTCBLOCK => reader.varuint().map(TemplateValue::Uint),
+                _ => {
+                    let len = reader.varuint()?;
+                    reader.take(len as usize).map(TemplateValue::from)
+                }
+            })
+            .collect::<Result<Vec<_>, CompilationError>>()?;
+        let primitive = match op {
+            INTCBLOCK => TypePrimitive::UInt64,
+            _ => TypePrimitive::Byteslice,
+        };
+        for (i, template) in block(templates, primitive).enumerate() {
+            *entries
+                .get_mut(i)
+                .ok_or(CompilationError::MalformedBytecode(
+                    "constant block is missing template entries",
+                ))? = value(template, values)?.clone();
+        }
+        patched.push(op);
+        write_varuint(&mut patched, entries.len() as u64);
+        for entry in entries {
+            match entry {
+                TemplateValue::Uint(i) => write_varuint(&mut patched, i),
+                TemplateValue::Bytes(bytes) => {
+                    write_varuint(&mut patched, bytes.len() as u64);
+                    patched.extend(bytes);
+                }
+            }
+        }
+    }
+    patched.extend(&program[reader.pos..]);
+    Ok(patched)
+}
+
+struct Reader<'a> {
+    program: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CompilationError> {
+        let bytes = self.program.get(self.pos..self.pos + len).ok_or(
+            CompilationError::MalformedBytecode("unexpected end of program"),
+        )?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn varuint(&mut self) -> Result<u64, CompilationError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return Ok(value);
+            }
+        }
+        Err(CompilationError::MalformedBytecode("varuint is too long"))
+    }
+}
+
+fn write_varuint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{compilation_error::CompilationError, typing::TypePrimitive};
+
+    use super::{
+        constant_blocks, load_template, substitute_bytecode, substitute_teal, Template,
+        TemplateValue,
+    };
+
+    fn templates() -> Vec<Template> {
+        vec![
+            Template {
+                name: "EXPIRY".to_string(),
+                primitive: TypePrimitive::UInt64,
+            },
+            Template {
+                name: "RECEIVER".to_string(),
+                primitive: TypePrimitive::Byteslice,
+            },
+            Template {
+                name: "FEE".to_string(),
+                primitive: TypePrimitive::UInt64,
+            },
+        ]
+    }
+
+    fn values() -> BTreeMap<String, TemplateValue> {
+        BTreeMap::from([
+            ("EXPIRY".to_string(), 1000.into()),
+            ("RECEIVER".to_string(), "ab".into()),
+            ("FEE".to_string(), 1.into()),
+        ])
+    }
+
+    #[test]
+    fn teal() {
+        let templates = templates();
+        assert_eq!(
+            constant_blocks(&templates),
+            "intcblock TMPL_EXPIRY TMPL_FEE\nbytecblock TMPL_RECEIVER"
+        );
+        assert_eq!(
+            load_template(&templates, "FEE").unwrap(),
+            "intc 1 // TMPL_FEE"
+        );
+        let teal = format!(
+            "#pragma version 8\n{}\n{}\nlen",
+            constant_blocks(&templates),
+            load_template(&templates, "RECEIVER").unwrap()
+        );
+        let substituted = substitute_teal(&teal, &templates, &values()).unwrap();
+        println!("{substituted}");
+        assert_eq!(
+            substituted,
+            "#pragma version 8\nintcblock 1000 1\nbytecblock 0x6162\nbytec 0 // TMPL_RECEIVER\nlen"
+        );
+        assert!(matches!(
+            substitute_teal(&teal, &templates, &BTreeMap::new()),
+            Err(CompilationError::MissingTemplateValue(name)) if name == "EXPIRY"
+        ));
+        let mut wrong = values();
+        wrong.insert("FEE".to_string(), "1".into());
+        assert!(matches!(
+            substitute_teal(&teal, &templates, &wrong),
+            Err(CompilationError::TemplateTypeMismatch(name)) if name == "FEE"
+        ));
+    }
+
+    #[test]
+    fn bytecode() {
+        // #pragma version 8; intcblock 0 0; bytecblock 0x00; intc_0; bytec_0; len; ==
+        let program = [
+            0x08, 0x20, 0x02, 0x00, 0x00, 0x26, 0x01, 0x01, 0x00, 0x22, 0x28, 0x15, 0x12,
+        ];
+        let patched = substitute_bytecode(&program, &templates(), &values()).unwrap();
+        println!("{patched:02x?}");
+        assert_eq!(
+            patched,
+            [
+                0x08, 0x20, 0x02, 0xe8, 0x07, 0x01, 0x26, 0x01, 0x02, 0x61, 0x62, 0x22, 0x28, 0x15,
+                0x12
+            ]
+        );
+        assert!(substitute_bytecode(&program[..4], &templates(), &values()).is_err());
+    }
+}
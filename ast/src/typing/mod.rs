@@ -36,7 +36,7 @@ mod tests {
         a.unify(&mut b).unwrap();
         match a {
             TypeEnum::Var(ref tv) => match **tv.value.borrow() {
-                Some(TypeEnum::Simple(ref v)) if v == &TypePrimitive::UInt64 => {}
+                Some(TypeEnum::Simple(TypePrimitive::UInt64)) => {}
                 None => panic!("Type variable should be set"),
                 _ => panic!("Type variable set incorrectly"),
             },
@@ -89,6 +89,28 @@ mod tests {
         println!("{:?}", b);
     }
 
+    #[test]
+    fn tuple_inference() {
+        let tv = TypeVar::new();
+        let mut a = TypeEnum::Tuple(vec![
+            TypeEnum::Var(tv.clone()),
+            TypeEnum::Simple(TypePrimitive::Byteslice),
+        ]);
+        let mut b = TypeEnum::Tuple(vec![
+            TypeEnum::Simple(TypePrimitive::UInt64),
+            TypeEnum::Var(TypeVar::new()),
+        ]);
+        a.unify(&mut b).unwrap();
+        assert_eq!(
+            TypeEnum::Var(tv).resolved(),
+            TypeEnum::Simple(TypePrimitive::UInt64)
+        );
+        assert_eq!("(int, bytes)", b.resolved().to_string());
+
+        let mut c = TypeEnum::Tuple(vec![TypeEnum::Simple(TypePrimitive::UInt64)]);
+        assert!(a.unify(&mut c).is_err());
+    }
+
     #[test]
     fn type_display() {
         assert_eq!("int", TypeEnum::Simple(TypePrimitive::UInt64).to_string());
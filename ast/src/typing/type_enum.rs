@@ -7,6 +7,12 @@ pub enum TypeEnum {
     Simple(TypePrimitive),
     Arrow(Box<TypeEnum>, Box<TypeEnum>),
     Var(TypeVar),
+    /// Several values left on the stack by a single expression, the last one
+    /// on top
+    Tuple(Vec<TypeEnum>),
+    /// A value together with a flag on top of the stack reporting whether it
+    /// exists, as produced by `app_global_get_ex` and friends
+    Option(Box<TypeEnum>),
 }
 
 impl TypeEnum {
@@ -23,6 +29,15 @@ impl TypeEnum {
                     .chain(b.used_tvars().into_iter().filter(|e| !used.contains(e)))
                     .collect::<Vec<usize>>()
             }
+            TypeEnum::Tuple(elements) => elements.iter().fold(vec![], |mut used, e| {
+                for tvar in e.used_tvars() {
+                    if !used.contains(&tvar) {
+                        used.push(tvar);
+                    }
+                }
+                used
+            }),
+            TypeEnum::Option(a) => a.used_tvars(),
             _ => {
                 vec![]
             }
@@ -59,10 +74,33 @@ impl TypeEnum {
             (TypeEnum::Arrow(ref mut a1, ref mut a2), TypeEnum::Arrow(ref mut b1, ref mut b2)) => {
                 a1.unify(b1).and(a2.unify(b2))
             }
+            (TypeEnum::Tuple(a), TypeEnum::Tuple(b)) if a.len() == b.len() => a
+                .iter_mut()
+                .zip(b.iter_mut())
+                .try_for_each(|(a, b)| a.unify(b)),
+            (TypeEnum::Option(ref mut a), TypeEnum::Option(ref mut b)) => a.unify(b),
             (a, b) => Err(TypeError::IrreconcilableTypes(a.clone(), b.clone())),
         }
     }
 
+    /// Follows bound type variables to the type they have been unified with.
+    pub fn resolved(&self) -> TypeEnum {
+        match self {
+            TypeEnum::Var(v) => match **v.value.borrow() {
+                Some(ref value) => value.resolved(),
+                None => self.clone(),
+            },
+            TypeEnum::Arrow(a, b) => {
+                TypeEnum::Arrow(Box::new(a.resolved()), Box::new(b.resolved()))
+            }
+            TypeEnum::Tuple(elements) => {
+                TypeEnum::Tuple(elements.iter().map(TypeEnum::resolved).collect())
+            }
+            TypeEnum::Option(a) => TypeEnum::Option(Box::new(a.resolved())),
+            _ => self.clone(),
+        }
+    }
+
     pub fn contains(&self, other: &TypeVar) -> bool {
         match self {
             TypeEnum::Var(v) => {
@@ -74,30 +112,38 @@ impl TypeEnum {
                     }
             }
             TypeEnum::Arrow(a, b) => a.contains(other) || b.contains(other),
+            TypeEnum::Tuple(elements) => elements.iter().any(|e| e.contains(other)),
+            TypeEnum::Option(a) => a.contains(other),
             _ => false,
         }
     }
 
     fn stringify_with_tvars(&self, tvars: &Vec<usize>) -> String {
-        format!(
-            "{}",
-            match self {
-                TypeEnum::Simple(s) => format!("{}", s),
-                TypeEnum::Arrow(a, b) if matches!(**a, TypeEnum::Arrow(..)) => format!(
-                    "({}) -> {}",
-                    a.stringify_with_tvars(tvars),
-                    b.stringify_with_tvars(tvars)
-                ),
-                TypeEnum::Arrow(a, b) => format!(
-                    "{} -> {}",
-                    a.stringify_with_tvars(tvars),
-                    b.stringify_with_tvars(tvars)
-                ),
-                TypeEnum::Var(v) => format!(
-                    "'{}",
-                    (tvars.iter().position(|x| x == &v.id).unwrap() as u8 + ('a' as u8)) as char
-                ),
-            }
-        )
+        match self {
+            TypeEnum::Simple(s) => format!("{}", s),
+            TypeEnum::Arrow(a, b) if matches!(**a, TypeEnum::Arrow(..)) => format!(
+                "({}) -> {}",
+                a.stringify_with_tvars(tvars),
+                b.stringify_with_tvars(tvars)
+            ),
+            TypeEnum::Arrow(a, b) => format!(
+                "{} -> {}",
+                a.stringify_with_tvars(tvars),
+                b.stringify_with_tvars(tvars)
+            ),
+            TypeEnum::Var(v) => format!(
+                "'{}",
+                (tvars.iter().position(|x| x == &v.id).unwrap() as u8 + b'a') as char
+            ),
+            TypeEnum::Tuple(elements) => format!(
+                "({})",
+                elements
+                    .iter()
+                    .map(|e| e.stringify_with_tvars(tvars))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeEnum::Option(a) => format!("option<{}>", a.stringify_with_tvars(tvars)),
+        }
     }
 }
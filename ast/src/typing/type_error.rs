@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-use crate::expression::var::Var;
+use crate::expression::{inner_txn::TxnType, txn::Txn, var::Var};
 
 use super::{type_enum::TypeEnum, type_primitive::TypePrimitive, type_var::TypeVar};
 
@@ -16,6 +16,26 @@ pub enum TypeError {
     StackUnderflow(TypeEnum),
     #[error("Attempt to call a non-function expression: {0:?}")]
     NonFunctionApplication(TypeEnum),
+    #[error("Expression producing multiple values must be destructured: {0}")]
+    UndestructuredTuple(TypeEnum),
+    #[error("Value of a statement followed by another one must be used: {0}")]
+    DiscardedValue(TypeEnum),
+    #[error("Optional value must be handled with match, unwrap_or or is_some: {0}")]
+    UnhandledOption(TypeEnum),
     #[error("Unbound identifier: {0:?}")]
     UnboundIdentifier(Var),
+    #[error("Not a state variable: {0:?}")]
+    NotStateVariable(Var),
+    #[error("Array field {0:?} must be accessed with an index")]
+    MissingArrayIndex(Txn),
+    #[error("Field {0:?} is not an array field")]
+    NotAnArrayField(Txn),
+    #[error("Field {1:?} cannot be set on an inner {0:?} transaction")]
+    InvalidInnerTxnField(TxnType, Txn),
+    #[error("Field {0:?} is set more than once")]
+    DuplicateInnerTxnField(Txn),
+    #[error("Unknown event {0}")]
+    UnknownEvent(String),
+    #[error("{0} takes {1} arguments, but {2} were given")]
+    WrongArgumentCount(String, usize, usize),
 }
@@ -5,6 +5,9 @@ pub enum TypePrimitive {
     Void,
     UInt64,
     Byteslice,
+    /// Arbitrary-precision unsigned integer, stored as a big-endian byte string
+    /// (up to 64 bytes) and operated on with the byte math opcodes
+    BigUInt,
     Halt,
 }
 
@@ -17,6 +20,7 @@ impl Display for TypePrimitive {
                 TypePrimitive::Void => "<void>",
                 TypePrimitive::UInt64 => "int",
                 TypePrimitive::Byteslice => "bytes",
+                TypePrimitive::BigUInt => "biguint",
                 TypePrimitive::Halt => "<halt>",
             }
         )
@@ -20,6 +20,12 @@ impl PartialEq for TypeVar {
 
 static TYPE_VAR_ID: AtomicUsize = AtomicUsize::new(0);
 
+impl Default for TypeVar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TypeVar {
     pub fn new() -> Self {
         Self {
@@ -0,0 +1,76 @@
+// Generated from the `calculator` app spec. Do not edit.
+
+#[allow(unused_imports)]
+use rusteal_ast::{
+    abi::{AbiError, AbiValue},
+    client::{self, AppCall, State},
+    expression::constant::OnComplete,
+};
+
+/// A calculator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calculator {
+    pub app_id: u64,
+}
+
+impl Calculator {
+    pub const APPROVAL_PROGRAM: &'static str = "#pragma version 5\ntxn NumAppArgs\nbz bare2\ntxna ApplicationArgs 0\nbyte 0x43a8ca96\n==\nbnz method_addTo0\ntxna ApplicationArgs 0\nbyte \"(tQ\\x89\"\n==\nbnz method_type1\nerr // unknown method selector\nmethod_addTo0:\ntxn OnCompletion\nint NoOp\n==\ntxn OnCompletion\nint OptIn\n==\n||\nassert // OnCompletion not allowed\ntxn ApplicationID\nassert // cannot create\ntxna ApplicationArgs 1\nbtoi\nstore 0\ntxna ApplicationArgs 2\ndup\nlen\nint 32\n==\nassert // address has wrong length\nstore 1\nint 1\nitob\nbyte 0x151f7c75\nswap\nconcat\nlog\nint 1\nreturn\nmethod_type1:\ntxn OnCompletion\nint NoOp\n==\nassert // OnCompletion not allowed\ntxn ApplicationID\nassert // cannot create\ntxna ApplicationArgs 1\nbtoi\nstore 0\ntxna ApplicationArgs 2\nint 0\ngetbit\nstore 1\nint 1\nint 0\n!=\nint 128\n*\nitob\nextract 7 1\nbyte 0x151f7c75\nswap\nconcat\nlog\nint 1\nreturn\nbare2:\ntxn OnCompletion\nint NoOp\n==\nassert // OnCompletion not allowed\nint 1";
+    pub const CLEAR_PROGRAM: &'static str = "#pragma version 5\nint 0";
+    pub const GLOBAL_NUM_UINTS: u64 = 1;
+    pub const GLOBAL_NUM_BYTE_SLICES: u64 = 0;
+    pub const LOCAL_NUM_UINTS: u64 = 0;
+    pub const LOCAL_NUM_BYTE_SLICES: u64 = 0;
+
+    pub fn new(app_id: u64) -> Self {
+        Calculator { app_id }
+    }
+
+    /// Adds to an account's total.
+    ///
+    /// Calls `addTo(uint64,address)uint64`.
+    /// Read-only, so it may be simulated instead.
+    pub fn add_to(&self, on_complete: OnComplete, a: u64, who: [u8; 32]) -> Result<AppCall, AbiError> {
+        client::method_call(
+            self.app_id,
+            on_complete,
+            [67, 168, 202, 150],
+            vec![
+                ("uint64", AbiValue::from(a)),
+                ("address", AbiValue::from(who)),
+            ],
+        )
+    }
+
+    /// Decodes the value returned by `addTo` from the last log of the call.
+    pub fn add_to_return(log: &[u8]) -> Result<u64, AbiError> {
+        client::decode_return("uint64", log)
+    }
+
+    /// Calls `type(uint64,bool)bool`.
+    pub fn r#type(&self, r#match: u64, self_: bool) -> Result<AppCall, AbiError> {
+        client::method_call(
+            self.app_id,
+            OnComplete::NoOp,
+            [40, 116, 81, 137],
+            vec![
+                ("uint64", AbiValue::from(r#match)),
+                ("bool", AbiValue::from(self_)),
+            ],
+        )
+    }
+
+    /// Decodes the value returned by `type` from the last log of the call.
+    pub fn type_return(log: &[u8]) -> Result<bool, AbiError> {
+        client::decode_return("bool", log)
+    }
+
+    /// Value of global `lastResult`.
+    pub fn global_last_result(state: &State) -> Option<u64> {
+        state.get("lastResult".as_bytes())?.as_uint()
+    }
+
+    /// Name of the box holding `history[key]`.
+    pub fn history_box(key: u64) -> Vec<u8> {
+        ["history".as_bytes(), key.to_be_bytes().as_slice()].concat()
+    }
+}
@@ -2,26 +2,55 @@ extern crate pest;
 #[macro_use]
 extern crate pest_derive;
 
-use core::slice;
-use std::{collections::HashMap, str::FromStr, vec};
+use std::{str::FromStr, sync::LazyLock, vec};
 
+use base64::{engine::general_purpose::STANDARD, Engine};
 use parse_error::ParseError;
 use pest::{
     iterators::{Pair, Pairs},
+    pratt_parser::{Assoc, Op, PrattParser},
     Parser,
 };
 use rusteal_ast::{
+    abi::AbiType,
+    address::{base32_decode, Address},
+    context::Mode,
     contract::Contract,
+    event::Event,
     expression::{
-        apply::Apply, binary::Binary, cond::Cond, primitive::Primitive, seq::Seq, txn::Txn,
-        var::Var, Expression,
+        app_state::AppStateEx,
+        apply::Apply,
+        arg::Arg,
+        arithmetic::Arithmetic,
+        binary::Binary,
+        bind::Bind,
+        box_op::BoxOp,
+        byte_op::ByteOp,
+        cond::Cond,
+        constant::OnComplete,
+        crypto::Crypto,
+        global::Global,
+        if_else::If,
+        inner_txn::{InnerTxn, InnerTxnGroup, TxnType},
+        log::{Emit, Log},
+        operator::Operator,
+        option::{Match, OptionOp},
+        params::Balance,
+        primitive::{decode_hex, Primitive},
+        ret::Ret,
+        seq::Seq,
+        txn::{Gitxn, Gtxn, Itxn, Txn, Txna},
+        var::{Delete, Exists, LVal, RVal, Var},
+        Expr,
     },
+    lsig::LogicSig,
+    method::AbiMethod,
     program::Program,
     struct_def::StructDef,
+    template::Template,
     typing::TypePrimitive,
-    MAX_TEAL_VERSION,
+    DEFAULT_TEAL_VERSION, MAX_TEAL_VERSION,
 };
-use thiserror::Error;
 
 mod parse_error;
 
@@ -29,26 +58,31 @@ mod parse_error;
 #[grammar = "grammar.pest"]
 struct RustealParser;
 
-fn parse_identifier(pair: Pair<Rule>) -> Result<&str, ParseError> {
+fn parse_identifier(pair: Pair<'_, Rule>) -> Result<&str, ParseError<'_>> {
     match pair.as_rule() {
         Rule::identifier => Ok(pair.as_str()),
         _ => unreachable!(),
     }
 }
 
-fn parse_function_def(pair: Pair<Rule>) -> Result<Box<dyn Expression>, ParseError> {
-    todo!()
-}
-
-fn parse_cond_arm(
-    pair: Pair<Rule>,
-) -> Result<(Box<dyn Expression>, Box<dyn Expression>), ParseError> {
-    todo!()
+fn parse_cond_arm(pair: Pair<'_, Rule>) -> Result<(Expr, Expr), ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::cond_arm => {
+            let mut i = pair.into_inner();
+            let test = parse_expression(i.next().unwrap())?;
+            let body = parse_expression(i.next().unwrap())?;
+            Ok((test, body))
+        }
+        // `else` is the last arm, which always matches
+        Rule::else_branch => Ok((
+            Expr::Primitive(Primitive::UInt64(1)),
+            parse_expression(pair.into_inner().next().unwrap())?,
+        )),
+        _ => unreachable!(),
+    }
 }
 
-fn fold_cond(
-    mut i: vec::IntoIter<(Box<dyn Expression>, Box<dyn Expression>)>,
-) -> Option<Box<Cond>> {
+fn fold_cond(mut i: vec::IntoIter<(Expr, Expr)>) -> Option<Box<Cond>> {
     let next = i.next();
     match next {
         Some((test, expr)) => Some(Box::new(Cond(test, expr, fold_cond(i)))),
@@ -56,35 +90,85 @@ fn fold_cond(
     }
 }
 
-fn parse_cond_expression(pair: Pair<Rule>) -> Result<Box<dyn Expression>, ParseError> {
+fn parse_cond_expression(pair: Pair<'_, Rule>) -> Result<Expr, ParseError<'_>> {
     match pair.as_rule() {
         Rule::cond_expression => {
             let arms = pair
                 .into_inner()
-                .map(|p| parse_cond_arm(p))
-                .collect::<Result<Vec<_>, ParseError>>()?;
+                .map(parse_cond_arm)
+                .collect::<Result<Vec<_>, ParseError<'_>>>()?;
 
-            Ok(
-                fold_cond(arms.into_iter()).ok_or(ParseError::EmptyCondExpression)?
-                    as Box<dyn Expression>,
-            )
+            Ok(Expr::Cond(
+                fold_cond(arms.into_iter()).ok_or(ParseError::EmptyCondExpression)?,
+            ))
         }
         _ => unreachable!(),
     }
 }
 
-fn parse_literal_expression(pair: Pair<Rule>) -> Result<Primitive, ParseError> {
+/// Decodes a quoted string literal, resolving its JSON-style escapes.
+fn decode_string(literal: &str) -> Option<Vec<u8>> {
+    let mut decoded = String::new();
+    let mut chars = literal[1..literal.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        decoded.push(match c {
+            '\\' => match chars.next()? {
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let code = chars.by_ref().take(4).collect::<String>();
+                    char::from_u32(u32::from_str_radix(&code, 16).ok()?)?
+                }
+                c => c,
+            },
+            c => c,
+        });
+    }
+    Some(decoded.into_bytes())
+}
+
+fn parse_uint64<T: std::str::FromStr>(pair: Pair<'_, Rule>) -> Result<T, ParseError<'_>> {
+    pair.as_str()
+        .parse()
+        .map_err(|_| ParseError::InvalidInteger(pair.as_str()))
+}
+
+fn parse_literal_expression(pair: Pair<'_, Rule>) -> Result<Primitive, ParseError<'_>> {
     match pair.as_rule() {
         Rule::literal_expression => {
             let lit = pair.into_inner().next().unwrap();
             match lit.as_rule() {
-                Rule::uint64 => Ok(Primitive::UInt64(lit.as_str().parse().unwrap())),
+                Rule::uint64 => parse_uint64(lit).map(Primitive::UInt64),
                 Rule::boolean => Ok(Primitive::UInt64(if lit.as_str() == "false" {
                     0
                 } else {
                     1
                 })),
-                Rule::bytes => Ok(Primitive::Byteslice(lit.as_str().as_bytes().to_vec())),
+                Rule::bytes => decode_string(lit.as_str())
+                    .map(Primitive::Byteslice)
+                    .ok_or(ParseError::InvalidBytesLiteral(lit.as_str())),
+                Rule::hex_bytes => decode_hex(&lit.as_str()[2..])
+                    .map(Primitive::Byteslice)
+                    .ok_or(ParseError::InvalidBytesLiteral(lit.as_str())),
+                Rule::base64_bytes => STANDARD
+                    .decode(lit.clone().into_inner().as_str())
+                    .map(Primitive::Byteslice)
+                    .map_err(|_| ParseError::InvalidBytesLiteral(lit.as_str())),
+                Rule::base32_bytes => {
+                    let encoded = lit.clone().into_inner().as_str();
+                    base32_decode(encoded.trim_end_matches('='))
+                        .map(Primitive::Byteslice)
+                        .map_err(|_| ParseError::InvalidBytesLiteral(lit.as_str()))
+                }
+                Rule::address => {
+                    let encoded = lit.into_inner().next().unwrap().as_str();
+                    let address = Address::from_str(encoded)
+                        .map_err(|e| ParseError::InvalidAddress(encoded, e))?;
+                    Ok(Primitive::Byteslice(address.0.to_vec()))
+                }
                 _ => unreachable!(),
             }
         }
@@ -92,90 +176,580 @@ fn parse_literal_expression(pair: Pair<Rule>) -> Result<Primitive, ParseError> {
     }
 }
 
-fn parse_qualified_identifier(pair: Pair<Rule>) -> Result<Vec<&str>, ParseError> {
+enum Segment<'a> {
+    Identifier(&'a str),
+    Index(Expr),
+}
+
+fn parse_qualified_identifier(pair: Pair<'_, Rule>) -> Result<Vec<Segment<'_>>, ParseError<'_>> {
     match pair.as_rule() {
         Rule::qualified_identifier => pair
             .into_inner()
-            .map(|i| parse_identifier(i))
-            .collect::<Result<Vec<&str>, ParseError>>(),
+            .map(|p| match p.as_rule() {
+                Rule::expression => parse_expression(p).map(Segment::Index),
+                _ => parse_identifier(p).map(Segment::Identifier),
+            })
+            .collect::<Result<Vec<Segment<'_>>, ParseError<'_>>>(),
         _ => unreachable!(),
     }
 }
 
-fn parse_binary_operation(
-    lhs: Box<dyn Expression>,
-    pair: Pair<Rule>,
-) -> Result<Box<dyn Expression>, ParseError> {
+fn parse_txn_field(field: &str) -> Result<Txn, ParseError<'_>> {
+    Txn::from_str(field).map_err(|_| ParseError::UnknownTransactionField(field))
+}
+
+fn parse_parameter_field<T: FromStr>(field: &str) -> Result<T, ParseError<'_>> {
+    T::from_str(field).map_err(|_| ParseError::UnknownParameterField(field))
+}
+
+fn resolve_qualified_identifier<'a>(
+    as_str: &'a str,
+    segments: Vec<Segment<'a>>,
+) -> Result<Expr, ParseError<'a>> {
+    use Segment::*;
+
+    match &segments[..] {
+        [Identifier(i)] => Ok(Expr::RVal(RVal(Var::Bind(i.to_string())))),
+        // `Global.Field` is the `global` opcode, while `global.field` and
+        // `local[account].field` refer to the declared state schemas
+        [Identifier("Global"), Identifier(f)] => Global::from_str(f)
+            .map(Expr::Global)
+            .map_err(|_| ParseError::UnknownGlobalField(f)),
+        [Identifier("global"), Identifier(f)] => Ok(Expr::RVal(RVal(Var::Global(f.to_string())))),
+        [Identifier("local"), Index(account), Identifier(f)] => Ok(Expr::Apply(Box::new(Apply(
+            Expr::RVal(RVal(Var::Local(f.to_string()))),
+            account.clone(),
+        )))),
+        [Identifier("template"), Identifier(f)] => {
+            Ok(Expr::RVal(RVal(Var::Template(f.to_string()))))
+        }
+        [Identifier("box"), Identifier(f)] => Ok(Expr::RVal(RVal(Var::Box(f.to_string())))),
+        [Identifier("box"), Identifier(f), Index(key)] => Ok(Expr::Apply(Box::new(Apply(
+            Expr::RVal(RVal(Var::BoxMap(f.to_string()))),
+            key.clone(),
+        )))),
+        [Identifier("Txn"), Identifier(f)] => parse_txn_field(f).map(Expr::Txn),
+        [Identifier("Txn"), Identifier(f), Index(i)] => Ok(Expr::Apply(Box::new(Apply(
+            Expr::Txna(Txna(parse_txn_field(f)?)),
+            i.clone(),
+        )))),
+        [Identifier("Gtxn"), Index(g), Identifier(f)] => Ok(Expr::Apply(Box::new(Apply(
+            Expr::Gtxn(Gtxn {
+                field: parse_txn_field(f)?,
+                array: false,
+            }),
+            g.clone(),
+        )))),
+        [Identifier("Gtxn"), Index(g), Identifier(f), Index(i)] => {
+            Ok(Expr::Apply(Box::new(Apply(
+                Expr::Apply(Box::new(Apply(
+                    Expr::Gtxn(Gtxn {
+                        field: parse_txn_field(f)?,
+                        array: true,
+                    }),
+                    g.clone(),
+                ))),
+                i.clone(),
+            ))))
+        }
+        // results of the last submitted inner transaction (group)
+        [Identifier("Itxn"), Identifier(f)] => Ok(Expr::Itxn(Itxn {
+            field: parse_txn_field(f)?,
+            array: false,
+        })),
+        [Identifier("Itxn"), Identifier(f), Index(i)] => Ok(Expr::Apply(Box::new(Apply(
+            Expr::Itxn(Itxn {
+                field: parse_txn_field(f)?,
+                array: true,
+            }),
+            i.clone(),
+        )))),
+        [Identifier("Gitxn"), Index(g), Identifier(f)] => Ok(Expr::Apply(Box::new(Apply(
+            Expr::Gitxn(Gitxn {
+                field: parse_txn_field(f)?,
+                array: false,
+            }),
+            g.clone(),
+        )))),
+        [Identifier("Gitxn"), Index(g), Identifier(f), Index(i)] => Ok(apply_call(
+            Expr::Gitxn(Gitxn {
+                field: parse_txn_field(f)?,
+                array: true,
+            }),
+            vec![g.clone(), i.clone()],
+        )),
+        // state of other applications: `app[id].global.key`, `app[id].local[account].key`
+        [Identifier("app"), Index(app), Identifier("global"), Identifier(key)] => Ok(apply_call(
+            Expr::AppStateEx(AppStateEx::Global),
+            vec![
+                app.clone(),
+                Expr::Primitive(Primitive::Byteslice(key.as_bytes().to_vec())),
+            ],
+        )),
+        [Identifier("app"), Index(app), Identifier("local"), Index(account), Identifier(key)] => {
+            Ok(apply_call(
+                Expr::AppStateEx(AppStateEx::Local),
+                vec![
+                    account.clone(),
+                    app.clone(),
+                    Expr::Primitive(Primitive::Byteslice(key.as_bytes().to_vec())),
+                ],
+            ))
+        }
+        [Identifier("AssetHolding"), Index(account), Index(asset), Identifier(f)] => {
+            Ok(apply_call(
+                Expr::AssetHolding(parse_parameter_field(f)?),
+                vec![account.clone(), asset.clone()],
+            ))
+        }
+        [Identifier("AssetParams"), Index(asset), Identifier(f)] => Ok(apply_call(
+            Expr::AssetParams(parse_parameter_field(f)?),
+            vec![asset.clone()],
+        )),
+        [Identifier("AppParams"), Index(app), Identifier(f)] => Ok(apply_call(
+            Expr::AppParams(parse_parameter_field(f)?),
+            vec![app.clone()],
+        )),
+        [Identifier("AcctParams"), Index(account), Identifier(f)] => Ok(apply_call(
+            Expr::AcctParams(parse_parameter_field(f)?),
+            vec![account.clone()],
+        )),
+        _ => Err(ParseError::UnknownQualifiedIdentifier(as_str)),
+    }
+}
+
+/// Resolves `global.x`, `local[account].x`, `box.x` or `box.x[key]` for forms operating on the state
+/// variable itself rather than its value, e.g. `delete` and `exists`.
+fn parse_state_variable(
+    pair: Pair<'_, Rule>,
+    wrap: fn(Var) -> Expr,
+) -> Result<Expr, ParseError<'_>> {
+    let as_str = pair.as_str();
+    match resolve_qualified_identifier(as_str, parse_qualified_identifier(pair)?)? {
+        Expr::RVal(RVal(var @ (Var::Global(_) | Var::Box(_)))) => Ok(wrap(var)),
+        Expr::Apply(apply) => match *apply {
+            Apply(Expr::RVal(RVal(var @ (Var::Local(_) | Var::BoxMap(_)))), account) => {
+                Ok(Expr::Apply(Box::new(Apply(wrap(var), account))))
+            }
+            _ => Err(ParseError::NotStateVariable(as_str)),
+        },
+        _ => Err(ParseError::NotStateVariable(as_str)),
+    }
+}
+
+/// Maps record field names to transaction fields, e.g. `close_remainder_to`
+/// to `CloseRemainderTo` and `application_id` to `ApplicationID`.
+fn parse_inner_txn_field_name(name: &str) -> Result<Txn, ParseError<'_>> {
+    let field = name
+        .split('_')
+        .map(|word| match word {
+            "id" | "pk" | "url" => word.to_uppercase(),
+            _ => {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            }
+        })
+        .collect::<String>();
+    Txn::from_str(&field).map_err(|_| ParseError::UnknownTransactionField(name))
+}
+
+fn parse_inner_txn(pair: Pair<'_, Rule>) -> Result<InnerTxn, ParseError<'_>> {
     match pair.as_rule() {
-        Rule::binary_operation => {
+        Rule::inner_txn => {
             let mut i = pair.into_inner();
-            let operator = i.next().unwrap();
-            let rhs = parse_expression(i.next().unwrap())?;
-            match operator.as_str() {
-                ";" => Ok(Box::new(Seq(lhs, Some(rhs)))),
-                ">" => Ok(Box::new(Apply(
-                    Box::new(Apply(Box::new(Binary::GreaterThan), rhs)),
-                    lhs,
-                ))),
-                _ => todo!(),
+            let kind = TxnType::from_str(i.next().unwrap().as_str()).unwrap();
+            let mut fields = vec![];
+            for field in i {
+                let mut field = field.into_inner();
+                let name = parse_inner_txn_field_name(parse_identifier(field.next().unwrap())?)?;
+                let value = field.next().unwrap();
+                match value.as_rule() {
+                    // array fields take one entry per element
+                    Rule::inner_txn_array => {
+                        for element in value.into_inner() {
+                            fields.push((name.clone(), parse_expression(element)?));
+                        }
+                    }
+                    _ => fields.push((name, parse_expression(value)?)),
+                }
+            }
+            Ok(InnerTxn { kind, fields })
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn parse_builtin(name: &str) -> Result<Expr, ParseError<'_>> {
+    ByteOp::from_name(name)
+        .map(Expr::ByteOp)
+        .or_else(|| Crypto::from_name(name).map(Expr::Crypto))
+        .or_else(|| Arithmetic::from_name(name).map(Expr::Arithmetic))
+        .or_else(|| OptionOp::from_name(name).map(Expr::OptionOp))
+        .or_else(|| AppStateEx::from_name(name).map(Expr::AppStateEx))
+        .or_else(|| Balance::from_name(name).map(Expr::Balance))
+        .or_else(|| BoxOp::from_name(name).map(Expr::BoxOp))
+        .or_else(|| (name == "log").then_some(Expr::Log(Log)))
+        .or_else(|| (name == "arg").then_some(Expr::Arg(Arg)))
+        .ok_or(ParseError::UnknownFunction(name))
+}
+
+/// Applies arguments in call order, so `f(a, b)` becomes `Apply(Apply(f, a), b)`.
+fn apply_call(function: Expr, args: Vec<Expr>) -> Expr {
+    args.into_iter()
+        .fold(function, |f, arg| Expr::Apply(Box::new(Apply(f, arg))))
+}
+
+fn parse_apply_expression(pair: Pair<'_, Rule>) -> Result<Expr, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::apply_expression => {
+            let mut i = pair.into_inner();
+            let function = i.next().unwrap();
+            let as_str = function.as_str();
+            let mut segments = parse_qualified_identifier(function)?;
+            let args = i.map(parse_expression).collect::<Result<Vec<_>, _>>()?;
+
+            match segments.pop() {
+                Some(Segment::Identifier(name)) if segments.is_empty() => {
+                    Ok(apply_call(parse_builtin(name)?, args))
+                }
+                // method-like sugar: `x.len()` is `len(x)`
+                Some(Segment::Identifier(name)) => {
+                    let receiver = resolve_qualified_identifier(as_str, segments)?;
+                    Ok(apply_call(
+                        parse_builtin(name)?,
+                        std::iter::once(receiver).chain(args).collect(),
+                    ))
+                }
+                _ => Err(ParseError::UnknownFunction(as_str)),
             }
         }
         _ => unreachable!(),
     }
 }
 
-fn parse_expression(pair1: Pair<Rule>) -> Result<Box<dyn Expression>, ParseError> {
-    match pair1.as_rule() {
-        Rule::expression => {
-            let mut i = pair1.into_inner();
+fn parse_if_expression(pair: Pair<'_, Rule>) -> Result<Expr, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::if_expression => {
+            let mut i = pair.into_inner();
+            let test = parse_expression(i.next().unwrap())?;
+            let true_expression = parse_expression(i.next().unwrap())?;
+            let false_expression = match i.next() {
+                Some(else_branch) => parse_expression(else_branch.into_inner().next().unwrap())?,
+                None => return Err(ParseError::MissingElseBranch),
+            };
+            Ok(Expr::Apply(Box::new(Apply(
+                Expr::If(Box::new(If(true_expression, false_expression))),
+                test,
+            ))))
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn parse_match_expression(pair: Pair<'_, Rule>) -> Result<Expr, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::match_expression => {
+            let mut i = pair.into_inner();
+            let value = parse_expression(i.next().unwrap())?;
+            let mut some = None;
+            let mut none = None;
+            for arm in i {
+                match arm.as_rule() {
+                    Rule::some_arm => {
+                        let mut arm = arm.into_inner();
+                        let identifier = parse_identifier(arm.next().unwrap())?;
+                        some = Some((identifier, parse_expression(arm.next().unwrap())?));
+                    }
+                    _ => none = Some(parse_expression(arm.into_inner().next().unwrap())?),
+                }
+            }
+            let (identifier, some) = some.unwrap();
+            Ok(Expr::Match(Box::new(Match {
+                value,
+                identifier: identifier.to_string(),
+                some,
+                none: none.unwrap(),
+            })))
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn parse_method_call(receiver: Expr, pair: Pair<'_, Rule>) -> Result<Expr, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::method_call => {
+            let mut i = pair.into_inner();
+            let function = parse_builtin(parse_identifier(i.next().unwrap())?)?;
+            let args = i.map(parse_expression).collect::<Result<Vec<_>, _>>()?;
+            Ok(apply_call(
+                function,
+                std::iter::once(receiver).chain(args).collect(),
+            ))
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Turns the variable read on the left of `=` into a write of `value`.
+fn assign(target: Expr, value: Expr) -> Result<Expr, ParseError<'static>> {
+    let lval = match target {
+        Expr::RVal(RVal(var)) => Expr::LVal(LVal(var)),
+        Expr::Apply(apply) => match *apply {
+            Apply(Expr::RVal(RVal(var @ (Var::Local(_) | Var::BoxMap(_)))), account) => {
+                Expr::Apply(Box::new(Apply(Expr::LVal(LVal(var)), account)))
+            }
+            _ => return Err(ParseError::InvalidAssignmentTarget),
+        },
+        _ => return Err(ParseError::InvalidAssignmentTarget),
+    };
+    Ok(Expr::Apply(Box::new(Apply(lval, value))))
+}
+
+fn parse_binary_operation(
+    lhs: Expr,
+    operator: Pair<'_, Rule>,
+    rhs: Expr,
+) -> Result<Expr, ParseError<'_>> {
+    let binary = match operator.as_rule() {
+        Rule::op_assign => return assign(lhs, rhs),
+        Rule::op_concat => return Ok(apply_call(Expr::ByteOp(ByteOp::Concat), vec![lhs, rhs])),
+        Rule::op_eq => Binary::Equals,
+        Rule::op_ne => Binary::NotEquals,
+        Rule::op_gt => Binary::GreaterThan,
+        Rule::op_ge => Binary::GreaterThanEquals,
+        Rule::op_lt => Binary::LessThan,
+        Rule::op_le => Binary::LessThanEquals,
+        Rule::op_add => Binary::Add,
+        Rule::op_sub => Binary::Subtract,
+        Rule::op_mul => Binary::Multiply,
+        Rule::op_div => Binary::Divide,
+        Rule::op_rem => Binary::Modulo,
+        Rule::op_and => Binary::And,
+        Rule::op_or => Binary::Or,
+        _ => unreachable!(),
+    };
+    Ok(Expr::Apply(Box::new(Apply(
+        Expr::Apply(Box::new(Apply(Expr::Operator(Operator::new(binary)), rhs))),
+        lhs,
+    ))))
+}
+
+/// Infix operators from lowest to highest precedence; all are left
+/// associative except `=`.
+static PRATT_PARSER: LazyLock<PrattParser<Rule>> = LazyLock::new(|| {
+    PrattParser::new()
+        .op(Op::infix(Rule::op_assign, Assoc::Right))
+        .op(Op::infix(Rule::op_or, Assoc::Left))
+        .op(Op::infix(Rule::op_and, Assoc::Left))
+        .op(Op::infix(Rule::op_eq, Assoc::Left)
+            | Op::infix(Rule::op_ne, Assoc::Left)
+            | Op::infix(Rule::op_lt, Assoc::Left)
+            | Op::infix(Rule::op_le, Assoc::Left)
+            | Op::infix(Rule::op_gt, Assoc::Left)
+            | Op::infix(Rule::op_ge, Assoc::Left))
+        .op(Op::infix(Rule::op_add, Assoc::Left)
+            | Op::infix(Rule::op_sub, Assoc::Left)
+            | Op::infix(Rule::op_concat, Assoc::Left))
+        .op(Op::infix(Rule::op_mul, Assoc::Left)
+            | Op::infix(Rule::op_div, Assoc::Left)
+            | Op::infix(Rule::op_rem, Assoc::Left))
+});
+
+fn parse_expression(pair: Pair<'_, Rule>) -> Result<Expr, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::expression => PRATT_PARSER
+            .map_primary(parse_term)
+            .map_infix(|lhs, operator, rhs| parse_binary_operation(lhs?, operator, rhs?))
+            .parse(pair.into_inner()),
+        _ => unreachable!(),
+    }
+}
+
+fn parse_term(pair: Pair<'_, Rule>) -> Result<Expr, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::term => {
+            let mut i = pair.into_inner();
             let pair = i.next().unwrap();
 
             let as_str = pair.as_str();
-            let rule = pair.as_rule();
-            let expr = match rule {
+            let expr = match pair.as_rule() {
                 // parenthesized/bracketed expressions result in nesting
-                Rule::expression => parse_expression(i.next().unwrap()),
-                Rule::literal_expression => parse_literal_expression(i.next().unwrap())
-                    .map(|p| Box::new(p) as Box<dyn Expression>),
-                Rule::cond_expression => parse_cond_expression(i.next().unwrap()),
+                Rule::expression => parse_expression(pair),
+                Rule::sequence => parse_sequence(pair),
+                Rule::literal_expression => parse_literal_expression(pair).map(Expr::Primitive),
+                Rule::cond_expression => parse_cond_expression(pair),
+                Rule::apply_expression => parse_apply_expression(pair),
+                Rule::if_expression => parse_if_expression(pair),
+                Rule::match_expression => parse_match_expression(pair),
+                Rule::delete_expression => {
+                    parse_state_variable(pair.into_inner().next().unwrap(), |var| {
+                        Expr::Delete(Delete(var))
+                    })
+                }
+                Rule::exists_expression => {
+                    parse_state_variable(pair.into_inner().next().unwrap(), |var| {
+                        Expr::Exists(Exists(var))
+                    })
+                }
+                Rule::emit_expression => {
+                    let mut i = pair.into_inner();
+                    let event = parse_identifier(i.next().unwrap())?.to_string();
+                    let args = i.map(parse_expression).collect::<Result<Vec<_>, _>>()?;
+                    Ok(Expr::Emit(Box::new(Emit { event, args })))
+                }
+                Rule::inner_txn_group => pair
+                    .into_inner()
+                    .map(parse_inner_txn)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|group| Expr::InnerTxnGroup(Box::new(InnerTxnGroup(group)))),
+                Rule::inner_txn => parse_inner_txn(pair).map(|txn| Expr::InnerTxn(Box::new(txn))),
                 Rule::qualified_identifier => {
-                    let i_vec = parse_qualified_identifier(i.next().unwrap())?;
-                    match &i_vec[..] {
-                        [i] => Ok(Box::new(Var(i.to_string())) as Box<dyn Expression>),
-                        ["Txn", s] => Txn::from_str(s)
-                            .map(|v| Box::new(v) as Box<dyn Expression>)
-                            .map_err(|_| ParseError::UnknownQualifiedIdentifier(as_str)),
-                        _ => Err(ParseError::UnknownQualifiedIdentifier(as_str)),
-                    }
+                    resolve_qualified_identifier(as_str, parse_qualified_identifier(pair)?)
                 }
+                Rule::return_expression => Ok(apply_call(
+                    Expr::Ret(Ret),
+                    vec![parse_expression(pair.into_inner().next().unwrap())?],
+                )),
                 _ => unreachable!(),
+            }?;
+
+            i.try_fold(expr, parse_method_call)
+        }
+        _ => unreachable!(),
+    }
+}
+
+enum Statement<'a> {
+    Let(Vec<&'a str>, Expr, &'a str),
+    Expression(Expr),
+}
+
+fn parse_let_expression(pair: Pair<'_, Rule>) -> Result<Statement<'_>, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::let_expression => {
+            let as_str = pair.as_str();
+            let mut i = pair.into_inner();
+            let pattern = i.next().unwrap();
+            let identifiers = match pattern.as_rule() {
+                Rule::tuple_pattern => pattern
+                    .into_inner()
+                    .map(parse_identifier)
+                    .collect::<Result<Vec<_>, _>>()?,
+                _ => vec![parse_identifier(pattern)?],
             };
+            let value = parse_expression(i.next().unwrap())?;
+            Ok(Statement::Let(identifiers, value, as_str))
+        }
+        _ => unreachable!(),
+    }
+}
 
-            let bin_pair = i.next();
-            if let Some(bin_pair) = bin_pair {
-                match bin_pair {
-                    // Rule::binary_operation => {}
-                    _ => todo!(),
-                }
-            }
+/// Statements separated by `;` evaluate in order, with each `let` binding
+/// scoped over the statements that follow it.
+fn parse_sequence(pair: Pair<'_, Rule>) -> Result<Expr, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::sequence => {
+            let statements = pair
+                .into_inner()
+                .map(|p| match p.as_rule() {
+                    Rule::let_expression => parse_let_expression(p),
+                    _ => parse_expression(p).map(Statement::Expression),
+                })
+                .collect::<Result<Vec<_>, ParseError<'_>>>()?;
 
-            expr
+            statements
+                .into_iter()
+                .rev()
+                .try_fold(None, |tail, statement| {
+                    Ok(Some(match (statement, tail) {
+                        (Statement::Expression(head), tail) => match tail {
+                            Some(tail) => Expr::Seq(Box::new(Seq(head, Some(tail)))),
+                            None => head,
+                        },
+                        (Statement::Let(_, _, as_str), None) => {
+                            return Err(ParseError::MissingLetBody(as_str))
+                        }
+                        (Statement::Let(mut identifiers, value, _), Some(body)) => {
+                            Expr::Bind(Box::new(if identifiers.len() == 1 {
+                                Bind::Let {
+                                    identifier: identifiers.remove(0).to_string(),
+                                    value,
+                                    body,
+                                }
+                            } else {
+                                Bind::LetTuple {
+                                    identifiers: identifiers
+                                        .into_iter()
+                                        .map(str::to_string)
+                                        .collect(),
+                                    value,
+                                    body,
+                                }
+                            }))
+                        }
+                    }))
+                })
+                .map(|e| e.unwrap())
         }
         _ => unreachable!(),
     }
 }
 
-fn parse_prog(pair: Pair<Rule>) -> Result<(&str, Program), ParseError> {
+/// `#pragma version N`, selecting the TEAL version of a program.
+fn parse_pragma(pair: Pair<'_, Rule>) -> Result<u64, ParseError<'_>> {
+    let version = pair.into_inner().next().unwrap().as_str();
+    version
+        .parse()
+        .ok()
+        .filter(|v| (1..=MAX_TEAL_VERSION).contains(v))
+        .ok_or(ParseError::UnsupportedVersion(version))
+}
+
+fn parse_prog(pair: Pair<'_, Rule>) -> Result<(&str, Program), ParseError<'_>> {
+    let mode = match pair.as_rule() {
+        Rule::prog => Mode::Application,
+        Rule::lsig => Mode::Signature,
+        _ => unreachable!(),
+    };
     match pair.as_rule() {
-        Rule::prog => {
+        Rule::prog | Rule::lsig => {
             let mut i = pair.into_inner();
             let identifier = parse_identifier(i.next().unwrap())?;
-            let expression = parse_expression(i.next().unwrap())?;
+            let mut methods = vec![];
+            let mut expressions = vec![];
+            let mut version = DEFAULT_TEAL_VERSION;
+            for p in i {
+                match p.as_rule() {
+                    Rule::pragma => version = parse_pragma(p)?,
+                    Rule::abi_method => methods.push(parse_abi_method(p)?),
+                    Rule::function_def => {
+                        return Err(ParseError::Unsupported("function definitions"))
+                    }
+                    _ => expressions.push(parse_sequence(p)?),
+                }
+            }
+            let body = expressions
+                .into_iter()
+                .rev()
+                .reduce(|tail, head| Expr::Seq(Box::new(Seq(head, Some(tail)))));
             Ok((
                 identifier,
-                Program {
-                    version: MAX_TEAL_VERSION,
-                    body: expression,
+                match body {
+                    Some(body) => Program {
+                        version,
+                        body,
+                        methods,
+                        mode,
+                    },
+                    None => Program {
+                        version,
+                        methods,
+                        mode,
+                        ..Default::default()
+                    },
                 },
             ))
         }
@@ -183,7 +757,7 @@ fn parse_prog(pair: Pair<Rule>) -> Result<(&str, Program), ParseError> {
     }
 }
 
-fn parse_datatype(pair: Pair<Rule>) -> Result<TypePrimitive, ParseError> {
+fn parse_datatype(pair: Pair<'_, Rule>) -> Result<TypePrimitive, ParseError<'_>> {
     match (pair.as_rule(), pair.as_str()) {
         (Rule::datatype, "uint64") => Ok(TypePrimitive::UInt64),
         (Rule::datatype, "bytes") => Ok(TypePrimitive::Byteslice),
@@ -191,47 +765,205 @@ fn parse_datatype(pair: Pair<Rule>) -> Result<TypePrimitive, ParseError> {
     }
 }
 
-fn parse_typed_field(pair: Pair<Rule>) -> Result<(&str, TypePrimitive), ParseError> {
+enum FieldType {
+    /// A value, with its byte width if declared, e.g. `owner: bytes[32]`
+    Value(TypePrimitive, Option<usize>),
+    /// A box map, e.g. `balances: map<bytes, uint64>`
+    Map(TypePrimitive, TypePrimitive),
+}
+
+fn parse_template_def(pair: Pair<'_, Rule>) -> Result<(&str, Template), ParseError<'_>> {
+    let mut inner = pair.into_inner();
+    let name = parse_identifier(inner.next().unwrap())?;
+    let primitive = parse_datatype(inner.next().unwrap())?;
+    Ok((
+        name,
+        Template {
+            name: name.to_string(),
+            primitive,
+        },
+    ))
+}
+
+fn parse_typed_field(pair: Pair<'_, Rule>) -> Result<(&str, FieldType), ParseError<'_>> {
     match pair.as_rule() {
         Rule::typed_field => {
             let mut i = pair.into_inner();
             let identifier = parse_identifier(i.next().unwrap())?;
-            let datatype = parse_datatype(i.next().unwrap())?;
-            Ok((identifier, datatype))
+            let datatype = i.next().unwrap();
+            let field_type = match datatype.as_rule() {
+                Rule::map_type => {
+                    let mut types = datatype.into_inner();
+                    FieldType::Map(
+                        parse_datatype(types.next().unwrap())?,
+                        parse_datatype(types.next().unwrap())?,
+                    )
+                }
+                _ => FieldType::Value(
+                    parse_datatype(datatype)?,
+                    i.next().map(parse_uint64).transpose()?,
+                ),
+            };
+            Ok((identifier, field_type))
         }
         _ => unreachable!(),
     }
 }
 
-fn parse_struct_def(pair: Pair<Rule>) -> Result<StructDef, ParseError> {
+fn parse_struct_def(pair: Pair<'_, Rule>, packed: bool) -> Result<StructDef<'_>, ParseError<'_>> {
     match pair.as_rule() {
-        Rule::struct_def => Ok(StructDef {
-            fields: pair
-                .into_inner()
-                .map(|p| parse_typed_field(p))
-                .collect::<Result<HashMap<&str, TypePrimitive>, ParseError>>()?,
-        }),
+        Rule::struct_def => {
+            let mut struct_def = StructDef {
+                packed,
+                ..Default::default()
+            };
+            for field in pair.into_inner() {
+                match parse_typed_field(field)? {
+                    (identifier, FieldType::Value(datatype, width)) => {
+                        struct_def.fields.insert(identifier, datatype);
+                        struct_def.order.push(identifier);
+                        if let Some(width) = width {
+                            struct_def.widths.insert(identifier, width);
+                        }
+                    }
+                    (identifier, FieldType::Map(key, value)) => {
+                        struct_def.maps.insert(identifier, (key, value));
+                    }
+                }
+            }
+            Ok(struct_def)
+        }
         _ => unreachable!(),
     }
 }
 
-fn parse_schema(pair: Pair<Rule>) -> Result<(&str, StructDef), ParseError> {
+fn parse_schema(pair: Pair<'_, Rule>) -> Result<(&str, StructDef<'_>), ParseError<'_>> {
     match pair.as_rule() {
         Rule::schema => {
-            let mut i = pair.into_inner();
+            let mut i = pair.into_inner().peekable();
+            let packed = i
+                .next_if(|p| p.as_rule() == Rule::packed_attribute)
+                .is_some();
             let name = parse_identifier(i.next().unwrap())?;
-            let struct_def = parse_struct_def(i.next().unwrap())?;
+            let struct_def = parse_struct_def(i.next().unwrap(), packed)?;
             Ok((name, struct_def))
         }
         _ => unreachable!(),
     }
 }
 
-fn parse_contract(pairs: Pairs<Rule>) -> Result<Contract, ParseError> {
+fn parse_abi_type(pair: Pair<'_, Rule>) -> Result<AbiType, ParseError<'_>> {
+    let as_str = pair.as_str();
+    AbiType::from_str(as_str).map_err(|_| ParseError::InvalidAbiType(as_str))
+}
+
+fn parse_abi_arg(pair: Pair<'_, Rule>) -> Result<(String, AbiType), ParseError<'_>> {
+    let mut i = pair.into_inner();
+    let identifier = parse_identifier(i.next().unwrap())?.to_string();
+    Ok((identifier, parse_abi_type(i.next().unwrap())?))
+}
+
+/// Collects leading doc comment lines, without their `///` or `//!` markers.
+fn parse_doc_comments(pairs: &mut Pairs<'_, Rule>) -> Option<String> {
+    let mut lines = vec![];
+    while let Some(Rule::doc_comment | Rule::inner_doc_comment) = pairs.peek().map(|p| p.as_rule())
+    {
+        lines.push(pairs.next().unwrap().into_inner().next().unwrap().as_str());
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+fn parse_event_def(pair: Pair<'_, Rule>) -> Result<Event, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::event_def => {
+            let mut i = pair.into_inner();
+            let doc = parse_doc_comments(&mut i);
+            let name = parse_identifier(i.next().unwrap())?.to_string();
+            let args = i.map(parse_abi_arg).collect::<Result<Vec<_>, _>>()?;
+            Ok(Event { name, args, doc })
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn parse_abi_method(pair: Pair<'_, Rule>) -> Result<AbiMethod, ParseError<'_>> {
+    match pair.as_rule() {
+        Rule::abi_method => {
+            let mut i = pair.into_inner();
+            let doc = parse_doc_comments(&mut i);
+            let mut create = false;
+            let mut on_complete = vec![];
+            let mut creator_only = false;
+            let mut readonly = false;
+            while let Some(Rule::method_attribute) = i.peek().map(|p| p.as_rule()) {
+                let mut attribute = i.next().unwrap().into_inner();
+                let attribute_name = attribute.next().unwrap().as_str();
+                match attribute_name {
+                    "create" => create = true,
+                    "readonly" => readonly = true,
+                    "no_op" => on_complete.push(OnComplete::NoOp),
+                    "opt_in" => on_complete.push(OnComplete::OptIn),
+                    "close_out" => on_complete.push(OnComplete::CloseOut),
+                    "update" => on_complete.push(OnComplete::UpdateApplication),
+                    "delete" => on_complete.push(OnComplete::DeleteApplication),
+                    _ => return Err(ParseError::UnknownAttribute(attribute_name)),
+                }
+                if let Some(auth) = attribute.next() {
+                    if !matches!(attribute_name, "update" | "delete") {
+                        return Err(ParseError::UnexpectedAuth(attribute_name));
+                    }
+                    match auth.as_str() {
+                        "creator" => creator_only = true,
+                        _ => return Err(ParseError::UnknownAuth(auth.as_str())),
+                    }
+                }
+            }
+            let name = parse_identifier(i.next().unwrap())?.to_string();
+            let mut args = vec![];
+            let mut returns = None;
+            for p in i {
+                match p.as_rule() {
+                    Rule::abi_arg => args.push(parse_abi_arg(p)?),
+                    Rule::abi_type => returns = Some(parse_abi_type(p)?),
+                    _ => {
+                        return Ok(AbiMethod {
+                            name,
+                            args,
+                            returns,
+                            body: parse_sequence(p)?,
+                            doc,
+                            create,
+                            on_complete,
+                            creator_only,
+                            readonly,
+                        })
+                    }
+                }
+            }
+            unreachable!()
+        }
+        _ => unreachable!(),
+    }
+}
+
+pub fn parse(source: &str) -> Result<Contract<'_>, ParseError<'_>> {
+    let contract = RustealParser::parse(Rule::contract, source)
+        .map_err(|e| ParseError::Syntax(Box::new(e)))?
+        .next()
+        .unwrap();
+    parse_contract(contract.into_inner())
+}
+
+fn parse_contract(mut pairs: Pairs<'_, Rule>) -> Result<Contract<'_>, ParseError<'_>> {
+    let doc = parse_doc_comments(&mut pairs);
     let mut txn_approval: Option<Program> = None;
     let mut txn_clear: Option<Program> = None;
     let mut schema_global: Option<StructDef> = None;
     let mut schema_local: Option<StructDef> = None;
+    let mut schema_box: Option<StructDef> = None;
+    let mut events: Vec<Event> = vec![];
+    let mut lsigs: Vec<LogicSig> = vec![];
+    let mut templates: Vec<Template> = vec![];
 
     for pair in pairs {
         match pair.as_rule() {
@@ -239,7 +971,8 @@ fn parse_contract(pairs: Pairs<Rule>) -> Result<Contract, ParseError> {
                 let (name, prog) = parse_prog(pair)?;
                 let o = match name {
                     "approval" => &mut txn_approval,
-                    "clear" => &mut txn_clear,
+                    "clear" if prog.methods.is_empty() => &mut txn_clear,
+                    "clear" => return Err(ParseError::MethodsInClearProgram),
                     _ => return Err(ParseError::InvalidProgramName(name)),
                 };
                 match o {
@@ -247,27 +980,70 @@ fn parse_contract(pairs: Pairs<Rule>) -> Result<Contract, ParseError> {
                     None => *o = Some(prog),
                 }
             }
+            Rule::lsig => {
+                let (name, program) = parse_prog(pair)?;
+                if !program.methods.is_empty() {
+                    return Err(ParseError::MethodsInLogicSig(name));
+                }
+                if lsigs.iter().any(|lsig| lsig.name == name) {
+                    return Err(ParseError::DuplicateProgramName(name));
+                }
+                lsigs.push(LogicSig {
+                    name: name.to_string(),
+                    program,
+                });
+            }
             Rule::schema => {
                 let (name, schema) = parse_schema(pair)?;
                 let o = match name {
                     "global" => &mut schema_global,
                     "local" => &mut schema_local,
+                    "box" => &mut schema_box,
                     _ => return Err(ParseError::InvalidSchemaName(name)),
                 };
+                if let (Some(map), false) = (schema.maps.keys().next(), name == "box") {
+                    return Err(ParseError::MapOutsideBoxSchema(map));
+                }
                 match o {
                     Some(_) => return Err(ParseError::DuplicateSchemaName(name)),
                     None => *o = Some(schema),
                 }
             }
+            Rule::event_def => {
+                let name = pair
+                    .clone()
+                    .into_inner()
+                    .find(|p| p.as_rule() == Rule::identifier)
+                    .unwrap()
+                    .as_str();
+                let event = parse_event_def(pair)?;
+                if events.iter().any(|e| e.name == event.name) {
+                    return Err(ParseError::DuplicateEventName(name));
+                }
+                events.push(event);
+            }
+            Rule::template_def => {
+                let (name, template) = parse_template_def(pair)?;
+                if templates.iter().any(|t| t.name == name) {
+                    return Err(ParseError::DuplicateTemplateName(name));
+                }
+                templates.push(template);
+            }
+            Rule::EOI => {}
             _ => unreachable!(),
         }
     }
 
     Ok(Contract {
-        txn_approval: txn_approval.unwrap_or_else(|| Program::default()),
-        txn_clear: txn_clear.unwrap_or_else(|| Program::default()),
-        schema_global: schema_global.unwrap_or_else(|| StructDef::default()),
-        schema_local: schema_local.unwrap_or_else(|| StructDef::default()),
+        doc,
+        txn_approval: txn_approval.unwrap_or_default(),
+        txn_clear: txn_clear.unwrap_or_default(),
+        schema_global: schema_global.unwrap_or_default(),
+        schema_local: schema_local.unwrap_or_default(),
+        schema_box: schema_box.unwrap_or_default(),
+        events,
+        templates,
+        lsigs,
     })
 }
 
@@ -276,9 +1052,47 @@ mod tests {
     use std::fs;
 
     use pest::Parser;
+    use rusteal_ast::app_spec::AppSpec;
+    use rusteal_ast::description::ContractDescription;
+    use rusteal_ast::expression::constant::OnComplete;
+    use rusteal_ast::expression::{
+        apply::Apply,
+        binary::Binary,
+        global::Global,
+        operator::Operator,
+        primitive::Primitive,
+        txn::{Gtxn, Txn},
+        var::{RVal, Var},
+        Expr, Expression,
+    };
+    use rusteal_ast::template::{substitute_teal, TemplateValue};
+    use rusteal_ast::{apply, binop, int};
 
     use crate::Rule;
     use crate::RustealParser;
+    use crate::{parse_expression, parse_sequence};
+
+    fn parse_expression_str(source: &str) -> Expr {
+        let pair = RustealParser::parse(Rule::expression, source)
+            .expect("successful parse")
+            .next()
+            .unwrap();
+        parse_expression(pair).unwrap()
+    }
+
+    fn compile_expression_str(source: &str) -> String {
+        let e = parse_expression_str(source);
+        e.resolve(&Default::default()).unwrap();
+        e.compile_raw().unwrap()
+    }
+
+    fn parse_sequence_str(source: &str) -> Expr {
+        let pair = RustealParser::parse(Rule::sequence, source)
+            .expect("successful parse")
+            .next()
+            .unwrap();
+        parse_sequence(pair).unwrap()
+    }
 
     #[test]
     fn test() {
@@ -288,7 +1102,7 @@ mod tests {
             .next()
             .unwrap();
 
-        let pairs = file.into_inner();
+        let _pairs = file.into_inner();
 
         // for pair in pairs {
         //     println!("{:?}", pair);
@@ -299,4 +1113,589 @@ mod tests {
 
         // println!("{:?}", pairs);
     }
+
+    #[test]
+    fn gtxn() {
+        let e = parse_expression_str("Gtxn[Txn.GroupIndex - 1].Amount");
+        assert_eq!(
+            e,
+            apply!(
+                @fn Expr::Gtxn(Gtxn { field: Txn::Amount, array: false });
+                @arg binop!((Expr::Txn(Txn::GroupIndex)) - (int!(1)));
+            )
+        );
+        e.resolve(&Default::default()).unwrap();
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "txn GroupIndex\nint 1\n-\ngtxns Amount"
+        );
+
+        let e = parse_expression_str("Gtxn[0].ApplicationArgs[1]");
+        assert_eq!(e.compile_raw().unwrap(), "gtxna 0 ApplicationArgs 1");
+    }
+
+    #[test]
+    fn global_field_and_global_state() {
+        assert_eq!(
+            parse_expression_str("Global.Round"),
+            Expr::Global(Global::Round)
+        );
+        assert_eq!(
+            parse_expression_str("global.Round"),
+            Expr::RVal(RVal(Var::Global("Round".to_string())))
+        );
+        assert_eq!(
+            compile_expression_str("Global.CurrentApplicationID == 0"),
+            "global CurrentApplicationID\nint 0\n=="
+        );
+    }
+
+    #[test]
+    fn byte_functions() {
+        assert_eq!(
+            parse_expression_str("len(Txn.Note)"),
+            parse_expression_str("Txn.Note.len()")
+        );
+        assert_eq!(
+            compile_expression_str("Txn.Note ++ Txn.Sender"),
+            "txn Note\ntxn Sender\nconcat"
+        );
+        assert_eq!(
+            compile_expression_str("Txn.Note.extract(0, 8).btoi()"),
+            "txn Note\nextract 0 8\nbtoi"
+        );
+    }
+
+    #[test]
+    fn crypto_functions() {
+        assert_eq!(
+            compile_expression_str("sha256(Txn.Note) == Txn.Lease"),
+            "txn Note\nsha256\ntxn Lease\n=="
+        );
+        assert_eq!(
+            compile_expression_str("ecdsa_pk_decompress_secp256r1(Txn.Note)"),
+            "txn Note\necdsa_pk_decompress Secp256r1"
+        );
+    }
+
+    #[test]
+    fn biguint_operators() {
+        let e = parse_expression_str("(biguint(Txn.Fee) * biguint(Txn.Amount)) < biguint(5)");
+        e.resolve(&Default::default()).unwrap();
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "txn Fee\nitob\ntxn Amount\nitob\nb*\nint 5\nitob\nb<"
+        );
+    }
+
+    #[test]
+    fn let_destructuring() {
+        let e = parse_sequence_str("let (hi, lo) = mulw(Txn.Fee, Txn.Amount); hi + lo");
+        println!("{}", e.resolve(&Default::default()).unwrap());
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "txn Fee\ntxn Amount\nmulw\nstore 1\nstore 0\nload 0\nload 1\n+"
+        );
+
+        let e = parse_sequence_str("let x = Txn.Fee; x + 1;");
+        e.resolve(&Default::default()).unwrap();
+        assert_eq!(
+            e.compile_raw().unwrap(),
+            "txn Fee\nstore 0\nload 0\nint 1\n+"
+        );
+
+        let e = parse_sequence_str(
+            "let (x, y) = ecdsa_pk_decompress(Txn.Note); sha512_256(x ++ y) == Txn.Sender",
+        );
+        e.resolve(&Default::default()).unwrap();
+        assert!(e
+            .compile_raw()
+            .unwrap()
+            .starts_with("txn Note\necdsa_pk_decompress Secp256k1\nstore 1\nstore 0"));
+
+        let e = parse_sequence_str("let output = vrf_verify(Txn.Note, Txn.Lease, Txn.Sender); 1");
+        assert!(e.resolve(&Default::default()).is_err());
+
+        let e = parse_sequence_str("let x = mulw(Txn.Fee, 2); x");
+        assert!(e.resolve(&Default::default()).is_err());
+
+        let pair = RustealParser::parse(Rule::sequence, "let x = 1")
+            .unwrap()
+            .next()
+            .unwrap();
+        assert!(parse_sequence(pair).is_err());
+    }
+
+    #[test]
+    fn options() {
+        let e = parse_expression_str(
+            "app_global_get_ex(Txn.Applications[1], \"price\").unwrap_or(0) + 1",
+        );
+        println!("{}", e.resolve(&Default::default()).unwrap());
+
+        let e = parse_expression_str(
+            "match app_local_get_ex(0, Txn.ApplicationID, \"level\") { None => 0, Some(level) => level * 2 }",
+        );
+        println!("{}", e.resolve(&Default::default()).unwrap());
+        println!("{}", e.compile_raw().unwrap());
+
+        let e = parse_sequence_str("let matches = Txn.Fee; matches");
+        assert!(e.resolve(&Default::default()).is_ok());
+    }
+
+    #[test]
+    fn foreign_state_and_parameters() {
+        let e =
+            parse_expression_str("app[Txn.Applications[1]].local[Txn.Sender].level.unwrap_or(0)");
+        e.resolve(&Default::default()).unwrap();
+        assert!(e
+            .compile_raw()
+            .unwrap()
+            .starts_with("txn Sender\ntxna Applications 1\nbyte \"level\"\napp_local_get_ex"));
+
+        let e = parse_expression_str(
+            "AssetParams[Txn.XferAsset].AssetDecimals.unwrap_or(0) == 6 && is_some(AssetHolding[Txn.Sender][Txn.XferAsset].AssetBalance)",
+        );
+        e.resolve(&Default::default()).unwrap();
+
+        let e = parse_expression_str("AssetParams[Txn.XferAsset].AssetManager.unwrap_or(0)");
+        assert!(e.resolve(&Default::default()).is_err());
+
+        assert_eq!(
+            compile_expression_str("balance(Txn.Sender) - min_balance(Txn.Sender)"),
+            "txn Sender\nbalance\ntxn Sender\nmin_balance\n-"
+        );
+    }
+
+    #[test]
+    fn delete_and_exists() {
+        assert_eq!(
+            parse_expression_str("delete local[Txn.Sender].level")
+                .compile_raw()
+                .unwrap(),
+            "txn Sender\nbyte \"level\"\napp_local_del"
+        );
+        assert_eq!(
+            parse_expression_str("exists(global.owner)")
+                .compile_raw()
+                .unwrap(),
+            "int 0\nbyte \"owner\"\napp_global_get_ex\nswap\npop"
+        );
+        assert!(RustealParser::parse(Rule::expression, "delete Txn.Sender")
+            .map(|mut p| parse_expression(p.next().unwrap()).is_err())
+            .unwrap());
+    }
+
+    #[test]
+    fn packed_schema() {
+        let contract = crate::parse(
+            "#[packed] schema global { counter: uint64, owner: bytes[32] }
+            prog approval {
+                #pragma version 7
+                global.counter = global.counter + 1;
+                global.owner = Txn.Sender;
+                1
+            }",
+        )
+        .unwrap();
+        assert!(contract.schema_global.packed);
+        assert_eq!(contract.schema_global.widths["owner"], 32);
+        let compiled = contract.compile().unwrap();
+        println!("{}", compiled.approval);
+        assert_eq!(compiled.global_schema.num_byte_slices, 1);
+        assert!(compiled.approval.contains(
+            "txn Sender\ndup\nlen\nint 32\n==\nassert // owner has wrong length\nreplace2 8"
+        ));
+
+        let reordered =
+            crate::parse("#[packed] schema global { owner: bytes[32], counter: uint64 }").unwrap();
+        let layout = reordered.schema_global.packed_layout().unwrap().unwrap();
+        assert_eq!(layout.get("owner").unwrap().offset, 0);
+        assert_eq!(layout.get("counter").unwrap().offset, 32);
+    }
+
+    #[test]
+    fn boxes() {
+        let contract = crate::parse(
+            "schema box { config: bytes, balances: map<bytes, uint64> }
+            prog approval {
+                #pragma version 8
+                box.balances[Txn.Sender] = box.balances[Txn.Sender] + Txn.Amount;
+                if (exists(box.config)) { delete box.config } else { box.config = \"v1\" };
+                box_extract(\"config\", 0, 2) == \"v1\"
+            }",
+        )
+        .unwrap();
+        let compiled = contract.compile().unwrap();
+        println!("{}", compiled.approval);
+        assert!(compiled.approval.contains(
+            "byte \"balances\"\ntxn Sender\nconcat\nbox_get\nassert // box does not exist\nbtoi\ntxn Amount\n+\nitob\nbox_put"
+        ));
+
+        assert!(crate::parse("schema global { balances: map<bytes, uint64> }").is_err());
+    }
+
+    #[test]
+    fn inner_transactions() {
+        let contract = crate::parse(
+            "prog approval {
+                #pragma version 6
+                pay { receiver: Txn.Sender, amount: 1000, fee: 0 };
+                group {
+                    axfer { xfer_asset: 7, asset_receiver: Txn.Sender, asset_amount: 1 },
+                    appl { application_id: 8, application_args: [Txn.Sender, Txn.Note] },
+                };
+                Itxn.ApplicationID == Gitxn[0].XferAsset
+            }",
+        )
+        .unwrap();
+        let compiled = contract.compile().unwrap();
+        println!("{}", compiled.approval);
+        assert!(compiled.approval.contains(
+            "itxn_begin\nint pay\nitxn_field TypeEnum\ntxn Sender\nitxn_field Receiver\nint 1000\nitxn_field Amount\nint 0\nitxn_field Fee\nitxn_submit"
+        ));
+        assert!(compiled
+            .approval
+            .contains("itxn_field ApplicationArgs\ntxn Note\nitxn_field ApplicationArgs"));
+        assert!(compiled
+            .approval
+            .contains("itxn ApplicationID\ngitxn 0 XferAsset"));
+
+        let contract = crate::parse("prog approval { pay { xfer_asset: 7 }; 1 }").unwrap();
+        assert!(contract.compile().is_err());
+        assert!(crate::parse("prog approval { pay { colour: 7 }; 1 }").is_err());
+    }
+
+    #[test]
+    fn events() {
+        let contract = crate::parse(
+            "event Transfer(from: address, to: address, amount: uint64)
+            prog approval {
+                log(Txn.Note);
+                emit Transfer(Txn.Sender, Txn.Receiver, Txn.Amount);
+                1
+            }",
+        )
+        .unwrap();
+        let compiled = contract.compile().unwrap();
+        println!("{}", compiled.approval);
+        assert!(compiled.approval.contains("txn Note\nlog"));
+        assert_eq!(
+            compiled.events[0].signature(),
+            "Transfer(address,address,uint64)"
+        );
+
+        let unknown = crate::parse("prog approval { emit Missing(1); 1 }").unwrap();
+        assert!(unknown.compile().is_err());
+        assert!(crate::parse("event A(x: uint64) event A(y: bool)").is_err());
+    }
+
+    #[test]
+    fn abi_methods() {
+        let contract = crate::parse(
+            "prog approval {
+                abi fn add(a: uint64, b: uint64) -> uint64 { a + b }
+                abi fn greet(name: string, tags: (uint8, bool[2])) -> string { \"hi\" ++ name }
+                abi fn ping() { log(Txn.Note) }
+                Txn.ApplicationID == 0
+            }",
+        )
+        .unwrap();
+        assert_eq!(
+            contract.txn_approval.methods[0].selector(),
+            [0xfe, 0x6b, 0xdf, 0x69]
+        );
+        let compiled = contract.compile().unwrap();
+        println!("{}", compiled.approval);
+        assert!(compiled
+            .approval
+            .contains("txna ApplicationArgs 1\nextract 2 0\nstore 0"));
+
+        let mistyped = crate::parse("prog approval { abi fn f() -> uint64 { Txn.Sender } }");
+        assert!(mistyped.unwrap().compile().is_err());
+        assert!(crate::parse("prog clear { abi fn f() { log(Txn.Note) } }").is_err());
+        assert!(crate::parse("prog approval { abi fn f(x: uint7) { log(Txn.Note) } }").is_err());
+    }
+
+    #[test]
+    fn contract_description() {
+        let contract = crate::parse(
+            "//! Keeps a running total.
+            /// Logged on every addition
+            event Added(total: uint64)
+            prog approval {
+                /// Adds to the total.
+                /// @param amount what to add
+                abi fn add(amount: uint64) -> uint64 { amount }
+                1
+            }",
+        )
+        .unwrap();
+        let compiled = contract.compile().unwrap();
+        let description = ContractDescription::new("Counter", &compiled);
+        let json = description.to_json();
+        println!("{json}");
+        assert_eq!(description.desc.as_deref(), Some("Keeps a running total."));
+        assert_eq!(
+            description.events[0].desc.as_deref(),
+            Some("Logged on every addition")
+        );
+        let add = description.method("add").unwrap();
+        assert_eq!(add.desc.as_deref(), Some("Adds to the total."));
+        assert_eq!(add.args[0].desc.as_deref(), Some("what to add"));
+        assert_eq!(ContractDescription::from_json(&json).unwrap(), description);
+    }
+
+    #[test]
+    fn lifecycle_attributes() {
+        let contract = crate::parse(
+            "prog approval {
+                #[create]
+                abi fn create() { log(Txn.Note) }
+                #[update(auth = creator)]
+                #[delete]
+                abi fn upgrade() { log(Txn.Note) }
+                #[readonly]
+                abi fn peek() -> uint64 { 1 }
+                1
+            }",
+        )
+        .unwrap();
+        let spec = AppSpec::new("Lifecycle", &contract).unwrap();
+        println!("{}", spec.to_json());
+        assert_eq!(spec.methods[0].actions.create, vec![OnComplete::NoOp]);
+        assert!(spec.methods[0].actions.call.is_empty());
+        assert_eq!(
+            spec.methods[1].actions.call,
+            vec![OnComplete::UpdateApplication, OnComplete::DeleteApplication]
+        );
+        assert!(contract.txn_approval.methods[1].creator_only);
+        assert!(spec.methods[2].readonly);
+
+        let unknown = "prog approval { #[destroy] abi fn f() { log(Txn.Note) } }";
+        assert!(matches!(
+            crate::parse(unknown),
+            Err(crate::ParseError::UnknownAttribute("destroy"))
+        ));
+        let auth = "prog approval { #[update(auth = anyone)] abi fn f() { log(Txn.Note) } }";
+        assert!(matches!(
+            crate::parse(auth),
+            Err(crate::ParseError::UnknownAuth("anyone"))
+        ));
+        let readonly = "prog approval { #[readonly(auth = creator)] abi fn f() { log(Txn.Note) } }";
+        assert!(matches!(
+            crate::parse(readonly),
+            Err(crate::ParseError::UnexpectedAuth("readonly"))
+        ));
+    }
+
+    #[test]
+    fn logic_signatures() {
+        let contract = crate::parse(
+            "lsig escrow { sha256(arg(0)) == Txn.Note }
+            prog approval { 1 }",
+        )
+        .unwrap();
+        let compiled = contract.compile().unwrap();
+        println!("{}", compiled.lsigs["escrow"]);
+        assert!(compiled.lsigs["escrow"].contains("arg 0\nsha256"));
+
+        let logged = crate::parse("lsig escrow { log(Txn.Note); 1 }").unwrap();
+        assert!(matches!(
+            logged.compile(),
+            Err(rusteal_ast::compilation_error::CompilationError::UnsupportedMode(..))
+        ));
+        let methods = "lsig escrow { abi fn f() { log(Txn.Note) } }";
+        assert!(matches!(
+            crate::parse(methods),
+            Err(crate::ParseError::MethodsInLogicSig("escrow"))
+        ));
+        assert!(crate::parse("prog approval { arg(0) == Txn.Note }")
+            .unwrap()
+            .compile()
+            .is_err());
+    }
+
+    #[test]
+    fn templates() {
+        let contract = crate::parse(
+            "template RECEIVER: bytes
+            template EXPIRY: uint64
+            lsig escrow { #pragma version 8 (Txn.Receiver == template.RECEIVER) && (Txn.LastValid < template.EXPIRY) }",
+        )
+        .unwrap();
+        let compiled = contract.compile().unwrap();
+        let escrow = &compiled.lsigs["escrow"];
+        println!("{escrow}");
+        assert!(escrow.starts_with(
+            "#pragma version 8\nintcblock TMPL_EXPIRY\nbytecblock TMPL_RECEIVER\ntxn Receiver\nbytec 0 // TMPL_RECEIVER"
+        ));
+        let values = std::collections::BTreeMap::from([
+            ("RECEIVER".to_string(), TemplateValue::from(&[7u8; 32][..])),
+            ("EXPIRY".to_string(), TemplateValue::from(1000)),
+        ]);
+        let substituted = substitute_teal(escrow, &compiled.templates, &values).unwrap();
+        assert!(substituted.contains("intcblock 1000\nbytecblock 0x0707"));
+        assert_eq!(substituted.lines().count(), escrow.lines().count());
+
+        let spec = AppSpec::new("Escrow", &contract).unwrap();
+        assert_eq!(spec.template_variables["EXPIRY"].template_type, "AVMUint64");
+        assert_eq!(spec.source_info.approval.pc_offset_method, "cblocks");
+
+        let duplicate = "template EXPIRY: uint64 template EXPIRY: bytes";
+        assert!(matches!(
+            crate::parse(duplicate),
+            Err(crate::ParseError::DuplicateTemplateName("EXPIRY"))
+        ));
+        let assigned = "template EXPIRY: uint64 prog approval { template.EXPIRY = 1; 1 }";
+        assert!(crate::parse(assigned).unwrap().compile().is_err());
+    }
+
+    #[test]
+    fn address_literal() {
+        let zero = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAY5HFKQ";
+        let e = parse_expression_str(&format!("Txn.Receiver == addr\"{zero}\""));
+        e.resolve(&Default::default()).unwrap();
+        let compiled = e.compile(&Default::default(), &mut vec![]).unwrap();
+        println!("{compiled}");
+        assert_eq!(
+            compiled,
+            format!("txn Receiver\nbyte base64 {}=\n==", "A".repeat(43))
+        );
+
+        let corrupted = format!("prog approval {{ addr\"B{}\" == Txn.Sender }}", &zero[1..]);
+        assert!(matches!(
+            crate::parse(&corrupted),
+            Err(crate::ParseError::InvalidAddress(
+                _,
+                rusteal_ast::address::AddressError::InvalidChecksum
+            ))
+        ));
+    }
+
+    #[test]
+    fn byte_literals() {
+        let bytes = |source: &str| match parse_expression_str(source) {
+            Expr::Primitive(Primitive::Byteslice(bytes)) => bytes,
+            e => panic!("not a bytes literal: {e:?}"),
+        };
+        assert_eq!(
+            bytes(r#""a \"quoted\" \\ \n\u00e9""#),
+            "a \"quoted\" \\ \n\u{e9}".as_bytes()
+        );
+        assert_eq!(bytes("0x00ff10"), [0x00, 0xff, 0x10]);
+        assert_eq!(bytes("0x"), b"");
+        assert_eq!(bytes(r#"b64"aGVsbG8=""#), b"hello");
+        assert_eq!(bytes(r#"b32"NBSWY3DP""#), b"hello");
+        assert_eq!(bytes(r#"b32"NBSWY3DPEE======""#), b"hello!");
+
+        let compiled = parse_expression_str(r#""tab\t""#)
+            .compile(&Default::default(), &mut vec![])
+            .unwrap();
+        println!("{compiled}");
+        assert_eq!(compiled, r#"byte "tab\t""#);
+
+        for invalid in [r#"0x123"#, r#"b64"aGVsbG8""#, r#"b32"nbswy3dp""#] {
+            let source = format!("prog approval {{ {invalid} == Txn.Note }}");
+            assert!(matches!(
+                crate::parse(&source),
+                Err(crate::ParseError::InvalidBytesLiteral(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn operator_precedence() {
+        let compile = compile_expression_str;
+        let compiled = compile("Txn.Fee - 1 - 2");
+        println!("{compiled}");
+        assert_eq!(compiled, "txn Fee\nint 1\n-\nint 2\n-");
+        assert_eq!(
+            compile("Txn.Fee == 1 && Txn.Amount == 2"),
+            "txn Fee\nint 1\n==\ntxn Amount\nint 2\n==\n&&"
+        );
+        assert_eq!(
+            compile("1 + 2 * 3 < 10 || Txn.Fee / 2 % 3 > 0"),
+            "int 1\nint 2\nint 3\n*\n+\nint 10\n<\ntxn Fee\nint 2\n/\nint 3\n%\nint 0\n>\n||"
+        );
+        assert_eq!(compile("(Txn.Fee - 1) * 2"), "txn Fee\nint 1\n-\nint 2\n*");
+    }
+
+    #[test]
+    fn return_and_cond() {
+        let compiled = crate::parse("prog approval { return 1 }")
+            .unwrap()
+            .compile()
+            .unwrap();
+        println!("{}", compiled.approval);
+        assert!(compiled.approval.ends_with("int 1\nreturn"));
+
+        let compiled =
+            crate::parse("prog approval { cond { Txn.Fee == 1 => 2, Txn.Fee == 2 => 3 } else 4 }")
+                .unwrap()
+                .compile()
+                .unwrap();
+        println!("{}", compiled.approval);
+        assert_eq!(compiled.approval.matches("\nb endcond").count(), 2);
+
+        assert!(matches!(
+            crate::parse("prog approval { fn f() { 1 } 1 }"),
+            Err(crate::ParseError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn discarded_values() {
+        for source in [
+            "prog approval { Txn.Fee; 1 }",
+            "prog approval { mulw(1, 2); 1 }",
+        ] {
+            let compiled = crate::parse(source).unwrap().compile();
+            println!("{:?}", compiled.as_ref().err());
+            assert!(matches!(
+                compiled,
+                Err(rusteal_ast::compilation_error::CompilationError::TypeCheck(
+                    rusteal_ast::typing::TypeError::DiscardedValue(_)
+                ))
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_integers() {
+        for (source, literal) in [
+            (
+                "prog approval { 18446744073709551616 }",
+                "18446744073709551616",
+            ),
+            (
+                "schema global { owner: bytes[99999999999999999999] }",
+                "99999999999999999999",
+            ),
+        ] {
+            let parsed = crate::parse(source);
+            println!("{:?}", parsed.as_ref().err());
+            assert!(matches!(parsed, Err(crate::ParseError::InvalidInteger(l)) if l == literal));
+        }
+    }
+
+    #[test]
+    fn pragma_version() {
+        let source = "prog approval { #pragma version 10 box_resize(\"a\", 10); 1 }";
+        let compiled = crate::parse(source).unwrap().compile().unwrap();
+        println!("{}", compiled.approval);
+        assert!(compiled
+            .approval
+            .starts_with("#pragma version 10\nbyte \"a\"\nint 10\nbox_resize"));
+
+        let unversioned = crate::parse("prog approval { box_resize(\"a\", 10); 1 }").unwrap();
+        assert!(matches!(
+            unversioned.compile(),
+            Err(rusteal_ast::compilation_error::CompilationError::UnsupportedVersion(_, 10, _))
+        ));
+        assert!(matches!(
+            crate::parse("prog approval { #pragma version 11 1 }"),
+            Err(crate::ParseError::UnsupportedVersion("11"))
+        ));
+    }
 }
@@ -1,5 +1,8 @@
+use rusteal_ast::address::AddressError;
 use thiserror::Error;
 
+use crate::Rule;
+
 #[derive(Error, Debug)]
 pub enum ParseError<'a> {
     #[error("Invalid program name {0}")]
@@ -10,8 +13,54 @@ pub enum ParseError<'a> {
     InvalidSchemaName(&'a str),
     #[error("Duplicate schema name {0}")]
     DuplicateSchemaName(&'a str),
+    #[error("ABI methods may only be declared in the approval program")]
+    MethodsInClearProgram,
+    #[error("ABI methods cannot be declared in logic signature {0}")]
+    MethodsInLogicSig(&'a str),
+    #[error("Invalid bytes literal {0}")]
+    InvalidBytesLiteral(&'a str),
+    #[error("Invalid integer literal {0}")]
+    InvalidInteger(&'a str),
+    #[error("Invalid address {0}: {1}")]
+    InvalidAddress(&'a str, AddressError),
+    #[error("Invalid ABI type {0}")]
+    InvalidAbiType(&'a str),
+    #[error("Unknown attribute {0}")]
+    UnknownAttribute(&'a str),
+    #[error("Unknown auth {0}, only creator is supported")]
+    UnknownAuth(&'a str),
+    #[error("Attribute {0} does not take an auth, only update and delete do")]
+    UnexpectedAuth(&'a str),
+    #[error("Duplicate event name {0}")]
+    DuplicateEventName(&'a str),
+    #[error("Duplicate template variable {0}")]
+    DuplicateTemplateName(&'a str),
+    #[error("Box maps may only be declared in schema box: {0}")]
+    MapOutsideBoxSchema(&'a str),
+    #[error("If expression must have an else branch")]
+    MissingElseBranch,
+    #[error("Unsupported TEAL version {0}")]
+    UnsupportedVersion(&'a str),
+    #[error("{0} are not supported yet")]
+    Unsupported(&'static str),
     #[error("Cond expression must have at least one arm")]
     EmptyCondExpression,
+    #[error("Let binding must be followed by an expression: {0}")]
+    MissingLetBody(&'a str),
     #[error("Unknown qualified identifier {0}")]
     UnknownQualifiedIdentifier(&'a str),
+    #[error("Unknown transaction field {0}")]
+    UnknownTransactionField(&'a str),
+    #[error("Unknown global field {0}")]
+    UnknownGlobalField(&'a str),
+    #[error("Not a global or local state variable: {0}")]
+    NotStateVariable(&'a str),
+    #[error("Left-hand side of an assignment must be a variable")]
+    InvalidAssignmentTarget,
+    #[error("Unknown parameter field {0}")]
+    UnknownParameterField(&'a str),
+    #[error("Unknown function {0}")]
+    UnknownFunction(&'a str),
+    #[error("Syntax error: {0}")]
+    Syntax(Box<pest::error::Error<Rule>>),
 }